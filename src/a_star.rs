@@ -1,86 +1,368 @@
+use super::arena::Arena;
+use super::bucket::{BucketQueue, TieBreak};
 use super::heuristics::Heuristic;
+use super::progress::{Progress, Status, Ticker};
 use super::puzzle::Puzzle;
 use super::solution::Solution;
-use std::collections::{BinaryHeap, HashMap};
+use super::solver::{Limits, Outcome, Solver, Stats};
+use super::state::{Context, State};
+use super::zobrist::ZobristSet;
 
 const TRANSITION_COST: u32 = 1;
 
-pub fn reconstruct_path(
-    came_from: HashMap<Puzzle, Puzzle>,
-    current: Puzzle,
-) -> Vec<Puzzle> {
-    let mut path = vec![];
-    path.push(current.clone());
-
-    let mut iter = &current;
-    while came_from.contains_key(iter) {
-        iter = &came_from[iter];
-        path.push(iter.clone());
+// The open list has a bucket per value of f, a heavier weight only makes
+// it longer: the search is already about greedy at this one
+//
+pub const MAX_WEIGHT: u32 = 100;
+
+// What the open list is sorted on
+//
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Priority {
+    // f = g + h
+    Sum,
+    // f = h
+    Heuristic,
+    // f = g
+    Cost,
+    // f = g + w * h
+    Weighted(u32),
+}
+
+impl Priority {
+    fn f(self, g: u32, h: u32) -> u32 {
+        match self {
+            Priority::Sum => g + h,
+            Priority::Heuristic => h,
+            Priority::Cost => g,
+            Priority::Weighted(w) => g.saturating_add(w.saturating_mul(h)),
+        }
     }
-    path.reverse();
-    path
 }
 
 // [https://github.com/samueltardieu/pathfinding/blob/main/src/directed/astar.rs]
 //
-pub fn a_star(
+pub fn best_first(
     start: Puzzle,
-    end: Puzzle,
+    end: &Puzzle,
     h: &mut dyn Heuristic,
-) -> Option<Solution> {
-    let mut open_list = BinaryHeap::new();
-    let mut came_from = HashMap::new();
-    let mut closed_set = vec![];
-
-    open_list.push(Score {
-        puzzle: start.clone(),
-        g: 0,
-        f: h.first_time(&start, &end),
-    });
-
-    while let Some(current) = open_list.pop() {
-        if current.puzzle == end {
-            return Some(Solution {
-                path: reconstruct_path(came_from, current.puzzle),
-                total_opened: open_list.len() + closed_set.len() + 1,
-                max_states: open_list.len() + 1,
+    priority: Priority,
+    tie_break: TieBreak,
+    limits: &Limits,
+    progress: &mut dyn Progress,
+) -> Outcome {
+    let mut ticker = Ticker::new();
+    let mut context = Context::new(&start);
+    let mut open_list = BucketQueue::new(tie_break);
+    let mut arena = Arena::new();
+    let mut closed_set = ZobristSet::default();
+    let mut stats = Stats {
+        total_opened: 1,
+        max_states: 1,
+    };
+    let goal = State::from(end);
+    // a goal spec is only checked on the board itself
+    let exact = start.end.is_exact();
+
+    // uniform-cost search never needs the heuristic
+    let uses_h = priority != Priority::Cost;
+    let start_h = if uses_h { h.first_time(&start, end) } else { 0 };
+    let f = priority.f(0, start_h);
+    open_list.push(f, 0, (State::from(&start), arena.root(), 0, start_h));
+
+    while let Some((f, (current, node, g, parent_h))) = open_list.pop() {
+        if closed_set.contains(&current) {
+            continue;
+        }
+        let reached = if exact {
+            current == goal
+        } else {
+            context.load(&current).is_goal(end)
+        };
+        if reached {
+            return Outcome::Solved(Solution {
+                stats,
+                start,
+                moves: arena.moves(node),
             });
         }
-        closed_set.push(current.puzzle.clone());
-        for neighbor in current.puzzle.neighbors() {
-            if closed_set.contains(&neighbor) {
-                continue;
-            }
-            came_from.insert(neighbor.clone(), current.puzzle.clone());
-            let g = current.g + TRANSITION_COST;
-            let f = g + h.difference(&neighbor, &end);
-            open_list.push(Score {
-                puzzle: neighbor,
-                g,
-                f,
+        if limits.exceeded(stats.total_opened) {
+            return Outcome::Interrupted(stats);
+        }
+        if ticker.tick() {
+            progress.report(&Status::Frontier {
+                open: open_list.len(),
+                closed: closed_set.len(),
+                min_f: f,
+                rate: ticker.rate(),
             });
         }
+        let g = g + TRANSITION_COST;
+        context.neighbors(&current, |m, state, neighbor| {
+            if closed_set.contains(&state) {
+                return;
+            }
+            let h = if uses_h {
+                h.update(parent_h, neighbor, end)
+            } else {
+                0
+            };
+            let f = priority.f(g, h);
+            open_list.push(f, g, (state, arena.push(node, m), g, h));
+            stats.total_opened += 1;
+        });
+        closed_set.insert(current);
+        stats.max_states =
+            stats.max_states.max(open_list.len() + closed_set.len());
     }
-    None
+    Outcome::Unsolvable(stats)
 }
 
-#[derive(PartialEq, Eq)]
-struct Score {
-    puzzle: Puzzle,
-    g: u32,
-    f: u32,
+pub fn a_star(
+    start: Puzzle,
+    end: &Puzzle,
+    h: &mut dyn Heuristic,
+    limits: &Limits,
+    progress: &mut dyn Progress,
+) -> Outcome {
+    best_first(
+        start,
+        end,
+        h,
+        Priority::Sum,
+        TieBreak::HighG,
+        limits,
+        progress,
+    )
+}
+
+pub struct AStar {
+    pub tie_break: TieBreak,
 }
 
-use std::cmp::Ordering;
+impl Solver for AStar {
+    fn name(&self) -> &'static str {
+        "A*"
+    }
+
+    fn solve(
+        &mut self,
+        start: Puzzle,
+        end: &Puzzle,
+        h: &mut dyn Heuristic,
+        limits: &Limits,
+        progress: &mut dyn Progress,
+    ) -> Outcome {
+        best_first(
+            start,
+            end,
+            h,
+            Priority::Sum,
+            self.tie_break,
+            limits,
+            progress,
+        )
+    }
+}
+
+// Only looks at how close a state seems to be from the goal, fast but the
+// solution is not optimal
+//
+pub struct Greedy {
+    pub tie_break: TieBreak,
+}
+
+impl Solver for Greedy {
+    fn name(&self) -> &'static str {
+        "Greedy"
+    }
 
-impl Ord for Score {
-    fn cmp(&self, other: &Self) -> Ordering {
-        other.f.cmp(&self.f)
+    fn solve(
+        &mut self,
+        start: Puzzle,
+        end: &Puzzle,
+        h: &mut dyn Heuristic,
+        limits: &Limits,
+        progress: &mut dyn Progress,
+    ) -> Outcome {
+        best_first(
+            start,
+            end,
+            h,
+            Priority::Heuristic,
+            self.tie_break,
+            limits,
+            progress,
+        )
     }
 }
 
-impl PartialOrd for Score {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+// Trusts the heuristic `weight` times more than the cost so far, the
+// solution is at most that many times longer than the optimal one
+//
+pub struct WeightedAStar {
+    pub weight: u32,
+    pub tie_break: TieBreak,
+}
+
+impl Solver for WeightedAStar {
+    fn name(&self) -> &'static str {
+        "Weighted A*"
+    }
+
+    fn solve(
+        &mut self,
+        start: Puzzle,
+        end: &Puzzle,
+        h: &mut dyn Heuristic,
+        limits: &Limits,
+        progress: &mut dyn Progress,
+    ) -> Outcome {
+        best_first(
+            start,
+            end,
+            h,
+            Priority::Weighted(self.weight),
+            self.tie_break,
+            limits,
+            progress,
+        )
+    }
+}
+
+// Dijkstra, the heuristic is not used at all
+//
+pub struct UniformCost {
+    pub tie_break: TieBreak,
+}
+
+impl Solver for UniformCost {
+    fn name(&self) -> &'static str {
+        "Uniform-cost"
+    }
+
+    fn solve(
+        &mut self,
+        start: Puzzle,
+        end: &Puzzle,
+        h: &mut dyn Heuristic,
+        limits: &Limits,
+        progress: &mut dyn Progress,
+    ) -> Outcome {
+        best_first(
+            start,
+            end,
+            h,
+            Priority::Cost,
+            self.tie_break,
+            limits,
+            progress,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bfs::bfs;
+    use crate::fixtures::subject_1;
+    use crate::goal::{Goal, GoalSpec};
+    use crate::heuristics::{get_heuristic, HeuristicFunc};
+    use crate::ida_star::ida_star;
+    use crate::progress::Silent;
+
+    fn moves(outcome: Outcome, end: &Puzzle) -> usize {
+        let solution = outcome.solution().unwrap();
+        assert_eq!(solution.end(), *end);
+        solution.moves.len()
+    }
+
+    #[test]
+    fn test_priorities() {
+        let (start, goal) = subject_1();
+        let mut h = get_heuristic(HeuristicFunc::ManhattanDistance);
+        let limits = Limits::default();
+        let mut search = |priority, tie_break| {
+            let outcome = best_first(
+                start.clone(),
+                &goal,
+                h.as_mut(),
+                priority,
+                tie_break,
+                &limits,
+                &mut Silent,
+            );
+            moves(outcome, &goal)
+        };
+
+        for tie_break in [
+            TieBreak::LowG,
+            TieBreak::HighG,
+            TieBreak::Fifo,
+            TieBreak::Lifo,
+        ] {
+            assert_eq!(search(Priority::Sum, tie_break), 15);
+            assert_eq!(search(Priority::Cost, tie_break), 15);
+            assert!(search(Priority::Heuristic, tie_break) >= 15);
+            assert!(search(Priority::Weighted(2), tie_break) <= 30);
+        }
+        assert_eq!(
+            moves(bfs(start.clone(), &goal, &limits, &mut Silent), &goal),
+            15
+        );
+        assert_eq!(Priority::Weighted(u32::MAX).f(1, 2), u32::MAX);
+    }
+
+    #[test]
+    fn test_goal_spec() {
+        let shifted = Puzzle::from_matrix(
+            3,
+            vec![vec![1, 2, 3], vec![0, 8, 4], vec![7, 6, 5]],
+        );
+        let (start, snail) = subject_1();
+        let limits = Limits::default();
+        let mut h = get_heuristic(HeuristicFunc::LinearConflicts);
+        let mut search = |spec: &GoalSpec| {
+            let mut start = start.clone();
+            start.set_goal_spec(spec);
+            let end = spec.representative();
+            let found = a_star(
+                start.clone(),
+                &end,
+                h.as_mut(),
+                &limits,
+                &mut Silent,
+            )
+            .solution()
+            .unwrap();
+            let deepened = ida_star(
+                start,
+                &end,
+                h.as_mut(),
+                None,
+                &limits,
+                &mut Silent,
+            )
+            .solution()
+            .unwrap();
+            let goal = Goal::from_spec(spec);
+            assert!(goal.matches(&found.end().flat));
+            assert!(goal.matches(&deepened.end().flat));
+            assert_eq!(found.moves.len(), deepened.moves.len());
+            found.moves.len()
+        };
+
+        let to_snail = search(&GoalSpec::exact(&snail));
+        let to_shifted = search(&GoalSpec::exact(&shifted));
+        let mut both = GoalSpec::exact(&snail);
+        both.patterns.extend(GoalSpec::exact(&shifted).patterns);
+        let mut top_row = GoalSpec::exact(&snail);
+        for cell in &mut top_row.patterns[0][3..] {
+            *cell = None;
+        }
+
+        assert_eq!(to_snail, 15);
+        assert_eq!(search(&both), to_snail.min(to_shifted));
+        assert!(search(&top_row) < to_snail);
     }
 }
@@ -0,0 +1,85 @@
+use super::moves::Move;
+use std::convert::TryFrom;
+
+const ROOT: u32 = 0;
+
+struct Node {
+    parent: u32,
+    m: Move,
+}
+
+// Every node a search generates, as the index of its parent and the move
+// made from there. The path to any node is read back by walking the
+// parents, instead of keeping a map from each board to the previous one.
+//
+pub struct Arena {
+    nodes: Vec<Node>,
+}
+
+impl Arena {
+    pub fn new() -> Arena {
+        Arena {
+            nodes: vec![Node {
+                parent: ROOT,
+                m: Move::Hold,
+            }],
+        }
+    }
+
+    pub fn root(&self) -> usize {
+        ROOT as usize
+    }
+
+    pub fn push(&mut self, parent: usize, m: Move) -> usize {
+        let parent =
+            u32::try_from(parent).expect("Too many nodes in the arena !");
+        self.nodes.push(Node { parent, m });
+        self.nodes.len() - 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn moves(&self, mut node: usize) -> Vec<Move> {
+        let mut moves = vec![];
+        while node != ROOT as usize {
+            moves.push(self.nodes[node].m);
+            node = self.nodes[node].parent as usize;
+        }
+        moves.reverse();
+        moves
+    }
+}
+
+impl Default for Arena {
+    fn default() -> Arena {
+        Arena::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arena_moves() {
+        let mut arena = Arena::new();
+        let up = arena.push(arena.root(), Move::Up);
+        let left = arena.push(up, Move::Left);
+        let right = arena.push(up, Move::Right);
+        let down = arena.push(left, Move::Down);
+
+        assert_eq!(arena.moves(arena.root()), vec![]);
+        assert_eq!(arena.moves(right), vec![Move::Up, Move::Right]);
+        assert_eq!(
+            arena.moves(down),
+            vec![Move::Up, Move::Left, Move::Down]
+        );
+        assert_eq!(arena.len(), 5);
+    }
+}
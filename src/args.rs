@@ -2,19 +2,41 @@ extern crate clap;
 use clap::{App, Arg};
 
 use std::process;
+use std::thread;
+use std::time::Duration;
 
-use super::heuristics::HeuristicFunc;
-use super::puzzle::{Difficulty, PuzzleKind};
-use super::solution::Algorithm;
+use n_puzzle::a_star::MAX_WEIGHT;
+use n_puzzle::beam::DEFAULT_WIDTH;
+use n_puzzle::bucket::TieBreak;
+use n_puzzle::heuristics::HeuristicFunc;
+use n_puzzle::hierarchical::{DEFAULT_INNER, MAX_INNER};
+use n_puzzle::puzzle::{Difficulty, PuzzleKind};
+use n_puzzle::real_time::{Rule, DEFAULT_LOOKAHEAD, DEFAULT_TRIALS};
+use n_puzzle::sma_star::DEFAULT_MEMORY;
+use n_puzzle::solution::Algorithm;
+use n_puzzle::solver::Limits;
 
 #[derive(Debug)]
 pub struct Sia {
     pub file: Option<String>,
+    pub goal: Option<String>,
     pub kind: PuzzleKind,
     pub size: usize,
     pub heuristic: HeuristicFunc,
     pub difficulty: Difficulty,
     pub algorithm: Algorithm,
+    pub progress: bool,
+    pub limits: Limits,
+    pub table: Option<String>,
+    pub build_table: Option<String>,
+    pub improve: bool,
+    pub all_optimal: bool,
+    pub count_optimal: bool,
+    pub k_shortest: Option<usize>,
+    pub hint: bool,
+    pub pdb: Option<String>,
+    pub portfolio: bool,
+    pub budget: Option<Duration>,
 }
 
 fn not_supported(arg: &str, option: &str) -> ! {
@@ -40,6 +62,14 @@ fn clap_your_hands() -> Sia {
 				.takes_value(true)
 				.value_name("FILE")
 				.help("File with custom puzzle"))
+		.arg(
+			Arg::with_name("goal")
+				.long("goal")
+				.takes_value(true)
+				.value_name("FILE")
+				.conflicts_with_all(&["table", "build-table", "portfolio"])
+				.help("File with the goal, _ for a cell any tile may end in, several boards for several goals")
+		)
 		.arg(
 			Arg::with_name("kind")
 				.short("k")
@@ -77,8 +107,156 @@ fn clap_your_hands() -> Sia {
 				.short("a")
 				.long("algo")
 				.takes_value(true)
-				.value_name("ASTAR|IDASTAR")
+				.value_name("ASTAR|IDASTAR|RBFS|SMASTAR|GREEDY|UCS|BFS|HDASTAR|WASTAR|CONSTRUCTIVE|HIERARCHICAL|BEAM|LRTASTAR|RTAASTAR")
 				.help("Algorithm used to resolve puzzle")
+		)
+		.arg(
+			Arg::with_name("progress")
+				.long("progress")
+				.help("Show a live status line on stderr while searching")
+		)
+		.arg(
+			Arg::with_name("max-nodes")
+				.long("max-nodes")
+				.takes_value(true)
+				.value_name("NUMBER")
+				.help("Give up once the search has opened this many states")
+		)
+		.arg(
+			Arg::with_name("memory")
+				.long("memory")
+				.takes_value(true)
+				.value_name("NUMBER")
+				.help("Maximum number of states kept in memory by SMA*")
+		)
+		.arg(
+			Arg::with_name("threads")
+				.long("threads")
+				.takes_value(true)
+				.value_name("NUMBER")
+				.help("Threads sharing the search with HDASTAR, all the cores by default")
+		)
+		.arg(
+			Arg::with_name("tt-size")
+				.long("tt-size")
+				.takes_value(true)
+				.value_name("NUMBER")
+				.help("Entries of the IDA* transposition table, none by default. Run it again without one to compare Total opened")
+		)
+		.arg(
+			Arg::with_name("weight")
+				.long("weight")
+				.takes_value(true)
+				.value_name("NUMBER")
+				.help("How much more WASTAR trusts the heuristic than the cost so far, 2 by default and 100 at most")
+		)
+		.arg(
+			Arg::with_name("inner")
+				.long("inner")
+				.takes_value(true)
+				.value_name("NUMBER")
+				.help("Side of the square HIERARCHICAL solves optimally, from 2 to 4, 4 by default")
+		)
+		.arg(
+			Arg::with_name("width")
+				.long("width")
+				.takes_value(true)
+				.value_name("NUMBER")
+				.help("States BEAM keeps at each depth, 1000 by default")
+		)
+		.arg(
+			Arg::with_name("finish")
+				.long("finish")
+				.help("When BEAM stops getting closer to the goal, let CONSTRUCTIVE finish the solution instead of giving up")
+		)
+		.arg(
+			Arg::with_name("lookahead")
+				.long("lookahead")
+				.takes_value(true)
+				.value_name("NUMBER")
+				.help("Depth LRTASTAR looks ahead, or states RTAASTAR expands, before every move, 4 by default")
+		)
+		.arg(
+			Arg::with_name("trials")
+				.long("trials")
+				.takes_value(true)
+				.value_name("NUMBER")
+				.help("Runs LRTASTAR and RTAASTAR make on the same board, learning from the previous ones, until one learns nothing or 1000 runs")
+		)
+		.arg(
+			Arg::with_name("improve")
+				.long("improve")
+				.help("Shorten the solution found by cutting its loops and detours")
+		)
+		.arg(
+			Arg::with_name("all-optimal")
+				.long("all-optimal")
+				.conflicts_with_all(&["table", "portfolio", "count-optimal"])
+				.help("Print every optimal solution, found by IDA* with the heuristic")
+		)
+		.arg(
+			Arg::with_name("count-optimal")
+				.long("count-optimal")
+				.conflicts_with_all(&["table", "portfolio"])
+				.help("Only count the optimal solutions, for boards with too many to print")
+		)
+		.arg(
+			Arg::with_name("k-shortest")
+				.long("k-shortest")
+				.takes_value(true)
+				.value_name("NUMBER")
+				.conflicts_with_all(&["table", "portfolio", "all-optimal", "count-optimal"])
+				.help("Print the NUMBER shortest solutions that never go through the same board twice")
+		)
+		.arg(
+			Arg::with_name("hint")
+				.long("hint")
+				.conflicts_with_all(&["table", "portfolio", "goal", "all-optimal", "count-optimal", "k-shortest"])
+				.help("Print how many moves are left and every move that keeps the solution optimal")
+		)
+		.arg(
+			Arg::with_name("pdb")
+				.long("pdb")
+				.takes_value(true)
+				.value_name("FILE")
+				.requires("hint")
+				.help("Pattern database --hint uses up to 4x4, read from FILE, or built and saved there when FILE does not exist")
+		)
+		.arg(
+			Arg::with_name("portfolio")
+				.long("portfolio")
+				.conflicts_with("table")
+				.help("Race IDA* + LINEAR, A* + MANHATTAN and weighted A* + LINEAR on separate threads")
+		)
+		.arg(
+			Arg::with_name("budget")
+				.long("budget")
+				.takes_value(true)
+				.value_name("SECONDS")
+				.requires("portfolio")
+				.help("Stop the portfolio after this long and keep the best answer found")
+		)
+		.arg(
+			Arg::with_name("tie-break")
+				.long("tie-break")
+				.takes_value(true)
+				.value_name("LOW-G|HIGH-G|FIFO|LIFO")
+				.help("Which state A*, GREEDY and UCS expand first among the ones with the same f")
+		)
+		.arg(
+			Arg::with_name("table")
+				.long("table")
+				.takes_value(true)
+				.value_name("FILE")
+				.help("Solve with a distance table instead of searching")
+		)
+		.arg(
+			Arg::with_name("build-table")
+				.long("build-table")
+				.takes_value(true)
+				.value_name("FILE")
+				.conflicts_with("table")
+				.help("Build the distance table of every board up to 3x3 for the goal")
 		);
 
     let matches = clap_app.get_matches();
@@ -88,6 +266,10 @@ fn clap_your_hands() -> Sia {
     let file = input_file.map(|f| f.to_string());
     /*													*/
 
+    /* goal option										*/
+    let goal = matches.value_of("goal").map(|g| g.to_string());
+    /*													*/
+
     /* kind option										*/
     let input_kind = matches.value_of("kind").unwrap_or("CLASSIC");
     let kind = match input_kind {
@@ -129,22 +311,202 @@ fn clap_your_hands() -> Sia {
     };
     /*													*/
 
+    /* memory option 									*/
+    let input_memory = matches.value_of("memory");
+    let memory = match input_memory.map(|m| m.parse()) {
+        None => DEFAULT_MEMORY,
+        Some(Ok(m)) => m,
+        _ => not_supported(input_memory.unwrap(), "memory"),
+    };
+    /*													*/
+
+    /* threads option 								*/
+    let input_threads = matches.value_of("threads");
+    let threads = match input_threads.map(|t| t.parse()) {
+        None => thread::available_parallelism().map_or(1, |n| n.get()),
+        Some(Ok(t)) if t > 0 => t,
+        _ => not_supported(input_threads.unwrap(), "threads"),
+    };
+    /*													*/
+
+    /* tt-size option 								*/
+    let input_table_size = matches.value_of("tt-size");
+    let table_size = match input_table_size.map(|t| t.parse()) {
+        None => 0,
+        Some(Ok(t)) => t,
+        _ => not_supported(input_table_size.unwrap(), "tt-size"),
+    };
+    /*													*/
+
+    /* tie-break option 								*/
+    let input_tie_break =
+        matches.value_of("tie-break").unwrap_or("HIGH-G");
+    let tie_break = match input_tie_break {
+        "LOW-G" | "low-g" => TieBreak::LowG,
+        "HIGH-G" | "high-g" => TieBreak::HighG,
+        "FIFO" | "fifo" => TieBreak::Fifo,
+        "LIFO" | "lifo" => TieBreak::Lifo,
+        _ => not_supported(input_tie_break, "tie-break"),
+    };
+    /*													*/
+
+    /* weight option 								*/
+    let input_weight = matches.value_of("weight");
+    let weight = match input_weight.map(|w| w.parse()) {
+        None => 2,
+        Some(Ok(w)) if w > 0 && w <= MAX_WEIGHT => w,
+        _ => not_supported(input_weight.unwrap(), "weight"),
+    };
+    /*													*/
+
+    /* inner option 								*/
+    let input_inner = matches.value_of("inner");
+    let inner = match input_inner.map(|i| i.parse()) {
+        None => DEFAULT_INNER,
+        Some(Ok(i)) if (2..=MAX_INNER).contains(&i) => i,
+        _ => not_supported(input_inner.unwrap(), "inner"),
+    };
+    /*													*/
+
+    /* width option 								*/
+    let input_width = matches.value_of("width");
+    let width = match input_width.map(|w| w.parse()) {
+        None => DEFAULT_WIDTH,
+        Some(Ok(w)) if w > 0 => w,
+        _ => not_supported(input_width.unwrap(), "width"),
+    };
+    let finish = matches.is_present("finish");
+    /*													*/
+
+    /* lookahead option 								*/
+    let input_lookahead = matches.value_of("lookahead");
+    let lookahead = match input_lookahead.map(|l| l.parse()) {
+        None => DEFAULT_LOOKAHEAD,
+        Some(Ok(l)) if l > 0 => l,
+        _ => not_supported(input_lookahead.unwrap(), "lookahead"),
+    };
+    /*													*/
+
+    /* trials option 								*/
+    let input_trials = matches.value_of("trials");
+    let trials = match input_trials.map(|t| t.parse()) {
+        None => DEFAULT_TRIALS,
+        Some(Ok(t)) if t > 0 => t,
+        _ => not_supported(input_trials.unwrap(), "trials"),
+    };
+    /*													*/
+
     /* algorithm option 								*/
     let input_algorithm = matches.value_of("algorithm").unwrap_or("ASTAR");
     let algorithm = match input_algorithm {
-        "ASTAR" | "astar" => Algorithm::AStar,
-        "IDASTAR" | "idastar" => Algorithm::IDAStar,
+        "ASTAR" | "astar" => Algorithm::AStar { tie_break },
+        "IDASTAR" | "idastar" => Algorithm::IDAStar { table_size },
+        "RBFS" | "rbfs" => Algorithm::RBFS,
+        "SMASTAR" | "smastar" => Algorithm::SMAStar { memory },
+        "GREEDY" | "greedy" => Algorithm::Greedy { tie_break },
+        "UCS" | "ucs" => Algorithm::UniformCost { tie_break },
+        "BFS" | "bfs" => Algorithm::BFS,
+        "HDASTAR" | "hdastar" => Algorithm::HDAStar { threads },
+        "WASTAR" | "wastar" => {
+            Algorithm::WeightedAStar { weight, tie_break }
+        }
+        "CONSTRUCTIVE" | "constructive" => Algorithm::Constructive,
+        "BEAM" | "beam" => Algorithm::Beam { width, finish },
+        "LRTASTAR" | "lrtastar" => Algorithm::RealTime {
+            rule: Rule::Lrta,
+            lookahead,
+            trials,
+        },
+        "RTAASTAR" | "rtaastar" => Algorithm::RealTime {
+            rule: Rule::Rtaa,
+            lookahead,
+            trials,
+        },
+        "HIERARCHICAL" | "hierarchical" => {
+            Algorithm::Hierarchical { inner }
+        }
         _ => not_supported(input_algorithm, "algorithm"),
     };
     /*													*/
 
+    /* progress option 								*/
+    let progress = matches.is_present("progress");
+    /*													*/
+
+    /* max-nodes option 								*/
+    let max_nodes = matches.value_of("max-nodes").map(|input| match input
+        .parse()
+    {
+        Ok(m) => m,
+        _ => not_supported(input, "max-nodes"),
+    });
+    let limits = Limits {
+        max_nodes,
+        cancel: None,
+    };
+    /*													*/
+
+    /* table options 								*/
+    let table = matches.value_of("table").map(|t| t.to_string());
+    let build_table =
+        matches.value_of("build-table").map(|t| t.to_string());
+    /*													*/
+
+    /* improve option 								*/
+    let improve = matches.is_present("improve");
+    /*													*/
+
+    /* optimal solutions options 								*/
+    let all_optimal = matches.is_present("all-optimal");
+    let count_optimal = matches.is_present("count-optimal");
+    /*													*/
+
+    /* k-shortest option 								*/
+    let k_shortest =
+        matches
+            .value_of("k-shortest")
+            .map(|input| match input.parse() {
+                Ok(k) if k > 0 => k,
+                _ => not_supported(input, "k-shortest"),
+            });
+    /*													*/
+
+    /* hint option 								*/
+    let hint = matches.is_present("hint");
+    let pdb = matches.value_of("pdb").map(|p| p.to_string());
+    /*													*/
+
+    /* portfolio options 								*/
+    let portfolio = matches.is_present("portfolio");
+    let budget =
+        matches.value_of("budget").map(|input| {
+            match input.parse().map(Duration::try_from_secs_f64) {
+                Ok(Ok(b)) if !b.is_zero() => b,
+                _ => not_supported(input, "budget"),
+            }
+        });
+    /*													*/
+
     Sia {
         file,
+        goal,
         kind,
         size,
         heuristic,
         difficulty,
         algorithm,
+        progress,
+        limits,
+        table,
+        build_table,
+        improve,
+        all_optimal,
+        count_optimal,
+        k_shortest,
+        hint,
+        pdb,
+        portfolio,
+        budget,
     }
 }
 
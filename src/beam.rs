@@ -0,0 +1,318 @@
+use super::arena::Arena;
+use super::constructive::constructive;
+use super::heuristics::Heuristic;
+use super::moves::Move;
+use super::progress::{Progress, Status, Ticker};
+use super::puzzle::Puzzle;
+use super::solution::Solution;
+use super::solver::{Limits, Outcome, Solver, Stats};
+use super::state::{Context, State};
+use super::zobrist::{ZobristMap, ZobristSet};
+
+pub const DEFAULT_WIDTH: usize = 1000;
+
+// Layers per row of the board the beam may go on without getting closer
+// to the goal before it gives up
+//
+const PATIENCE: usize = 4;
+
+// A board can only come back an even number of moves later, the layers
+// kept to drop duplicates cover the shortest loops
+//
+const RECENT_LAYERS: usize = 16;
+
+// Where a beam that stopped getting closer to the goal gave up: the
+// closest board it kept and the moves to it
+//
+pub struct Stuck {
+    pub closest: Puzzle,
+    pub moves: Vec<Move>,
+    pub stats: Stats,
+}
+
+// [https://en.wikipedia.org/wiki/Beam_search]
+//
+// Breadth-first, but only the `width` states of each layer with the lowest
+// heuristic are expanded. Memory and time per layer stay the same whatever
+// the size of the board, the price is that the solution is not optimal.
+//
+// When no layer gets closer to the goal for a while, the search gives up
+// and tells where it got stuck.
+//
+pub fn beam(
+    start: Puzzle,
+    end: &Puzzle,
+    h: &mut dyn Heuristic,
+    width: usize,
+    limits: &Limits,
+    progress: &mut dyn Progress,
+) -> Result<Outcome, Stuck> {
+    let mut ticker = Ticker::new();
+    let mut context = Context::new(&start);
+    let mut arena = Arena::new();
+    let mut stats = Stats {
+        total_opened: 1,
+        max_states: 1,
+    };
+    let goal = State::from(end);
+    let patience = PATIENCE * start.n;
+
+    let first = State::from(&start);
+    if first == goal {
+        return Ok(Outcome::Solved(Solution {
+            stats,
+            start,
+            moves: vec![],
+        }));
+    }
+    // depth every recent state was last kept at
+    let mut recent = ZobristMap::default();
+    recent.insert(first.clone(), 0);
+    let start_h = h.first_time(&start, end);
+    // closest board kept so far, its heuristic and when it was found
+    let mut best = (first.clone(), arena.root());
+    let (mut best_h, mut improved) = (start_h, 0);
+    let mut layer = vec![(first, arena.root(), start_h)];
+
+    let mut depth = 0;
+    loop {
+        depth += 1;
+        if depth - improved > patience {
+            let (state, node) = best;
+            return Err(Stuck {
+                closest: context.load(&state).clone(),
+                moves: arena.moves(node),
+                stats,
+            });
+        }
+        let mut seen = ZobristSet::default();
+        let mut candidates: Vec<(State, usize, Move, u32)> = vec![];
+        let mut report = false;
+        for (state, node, parent_h) in &layer {
+            context.neighbors(state, |m, state, neighbor| {
+                stats.total_opened += 1;
+                report |= ticker.tick();
+                let kept = recent.get(&state).copied();
+                if seen.contains(&state)
+                    || kept.is_some_and(|d| depth - d <= RECENT_LAYERS)
+                {
+                    return;
+                }
+                let h = h.update(*parent_h, neighbor, end);
+                seen.insert(state.clone());
+                candidates.push((state, *node, m, h));
+            });
+        }
+        if let Some((_, node, m, _)) =
+            candidates.iter().find(|(state, ..)| *state == goal)
+        {
+            let node = arena.push(*node, *m);
+            return Ok(Outcome::Solved(Solution {
+                stats,
+                start,
+                moves: arena.moves(node),
+            }));
+        }
+        if candidates.is_empty() || limits.exceeded(stats.total_opened) {
+            return Ok(Outcome::Interrupted(stats));
+        }
+
+        candidates.sort_by_key(|&(_, _, _, h)| h);
+        candidates.truncate(width);
+        if report {
+            progress.report(&Status::Layer {
+                depth,
+                kept: candidates.len(),
+                min_h: candidates[0].3,
+                rate: ticker.rate(),
+            });
+        }
+        layer = candidates
+            .into_iter()
+            .map(|(state, node, m, h)| (state, arena.push(node, m), h))
+            .collect();
+        for (state, ..) in &layer {
+            recent.insert(state.clone(), depth);
+        }
+        if layer[0].2 < best_h {
+            best = (layer[0].0.clone(), layer[0].1);
+            best_h = layer[0].2;
+            improved = depth;
+        }
+        stats.max_states = stats.max_states.max(recent.len());
+        if depth % RECENT_LAYERS == 0 {
+            recent.retain(|_, d| depth - *d < RECENT_LAYERS);
+        }
+    }
+}
+
+pub struct Beam {
+    pub width: usize,
+    // whether the constructive solver finishes from where the beam got
+    // stuck, instead of giving up
+    pub finish: bool,
+    // moves the constructive solver made in the last solve
+    pub handed_over: usize,
+}
+
+impl Solver for Beam {
+    fn name(&self) -> &'static str {
+        "Beam"
+    }
+
+    fn solve(
+        &mut self,
+        start: Puzzle,
+        end: &Puzzle,
+        h: &mut dyn Heuristic,
+        limits: &Limits,
+        progress: &mut dyn Progress,
+    ) -> Outcome {
+        self.handed_over = 0;
+        let stuck = match beam(
+            start.clone(),
+            end,
+            h,
+            self.width,
+            limits,
+            progress,
+        ) {
+            Ok(outcome) => return outcome,
+            Err(stuck) if !self.finish => {
+                return Outcome::Interrupted(stuck.stats)
+            }
+            Err(stuck) => stuck,
+        };
+        let mut stats = stuck.stats;
+        match constructive(stuck.closest, end, limits) {
+            Outcome::Solved(rest) => {
+                stats.total_opened += rest.stats.total_opened;
+                self.handed_over = rest.moves.len();
+                let mut moves = stuck.moves;
+                moves.extend(rest.moves);
+                Outcome::Solved(Solution {
+                    stats,
+                    start,
+                    moves,
+                })
+            }
+            _ => Outcome::Interrupted(stats),
+        }
+    }
+
+    fn details(&self) -> Vec<String> {
+        match self.handed_over {
+            0 => vec![],
+            moves => vec![format!(
+                "Finished by the constructive solver: {}",
+                moves
+            )],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::subject_1;
+    use crate::heuristics::{get_heuristic, HeuristicFunc};
+    use crate::progress::Silent;
+    use crate::puzzle::{Difficulty, PuzzleKind};
+
+    #[test]
+    fn test_beam_solves_large_boards() {
+        let mut h = get_heuristic(HeuristicFunc::LinearConflicts);
+        for kind in [PuzzleKind::Classic, PuzzleKind::Snail] {
+            let goal = Puzzle::new(kind, 6);
+            let mut start =
+                Puzzle::new_randomized(kind, Difficulty::Hard, 6);
+            start.set_goal(&goal);
+            let solution = Beam {
+                width: 100,
+                finish: true,
+                handed_over: 0,
+            }
+            .solve(
+                start,
+                &goal,
+                h.as_mut(),
+                &Limits::default(),
+                &mut Silent,
+            )
+            .solution()
+            .unwrap();
+            assert_eq!(solution.end(), goal);
+        }
+    }
+
+    #[test]
+    fn test_stuck_beam_hands_over() {
+        let mut h = get_heuristic(HeuristicFunc::ManhattanDistance);
+        let goal = Puzzle::new(PuzzleKind::Classic, 6);
+        let mut start = Puzzle::new_randomized(
+            PuzzleKind::Classic,
+            Difficulty::Hard,
+            6,
+        );
+        start.set_goal(&goal);
+        let stuck = match beam(
+            start.clone(),
+            &goal,
+            h.as_mut(),
+            1,
+            &Limits::default(),
+            &mut Silent,
+        ) {
+            Ok(_) => panic!("a beam of one board should get stuck"),
+            Err(stuck) => stuck,
+        };
+        let mut board = start.clone();
+        for m in &stuck.moves {
+            m.apply(&mut board);
+        }
+        assert_eq!(board, stuck.closest);
+        assert_ne!(board, goal);
+
+        let mut solve = |finish| {
+            let mut solver = Beam {
+                width: 1,
+                finish,
+                handed_over: 0,
+            };
+            let outcome = solver.solve(
+                start.clone(),
+                &goal,
+                h.as_mut(),
+                &Limits::default(),
+                &mut Silent,
+            );
+            (outcome, solver.handed_over)
+        };
+
+        let (outcome, _) = solve(false);
+        assert!(matches!(outcome, Outcome::Interrupted(_)));
+        let (outcome, handed_over) = solve(true);
+        let solution = outcome.solution().unwrap();
+        assert_eq!(solution.end(), goal);
+        assert!(handed_over > 0 && handed_over <= solution.moves.len());
+    }
+
+    #[test]
+    fn test_wide_beam_is_breadth_first() {
+        let (start, goal) = subject_1();
+        let mut h = get_heuristic(HeuristicFunc::ManhattanDistance);
+        let solution = beam(
+            start,
+            &goal,
+            h.as_mut(),
+            usize::MAX,
+            &Limits::default(),
+            &mut Silent,
+        )
+        .ok()
+        .unwrap()
+        .solution()
+        .unwrap();
+        assert_eq!(solution.moves.len(), 15);
+    }
+}
@@ -0,0 +1,97 @@
+use super::arena::Arena;
+use super::heuristics::Heuristic;
+use super::progress::{Progress, Status, Ticker};
+use super::puzzle::Puzzle;
+use super::solution::Solution;
+use super::solver::{Limits, Outcome, Solver, Stats};
+use super::state::{Context, State};
+use super::zobrist::ZobristSet;
+use std::collections::VecDeque;
+
+// [https://en.wikipedia.org/wiki/Breadth-first_search]
+//
+// Plain FIFO queue, states are expanded layer by layer so the first time
+// the goal is generated it is at the lowest depth
+//
+pub fn bfs(
+    start: Puzzle,
+    end: &Puzzle,
+    limits: &Limits,
+    progress: &mut dyn Progress,
+) -> Outcome {
+    let mut ticker = Ticker::new();
+    let mut context = Context::new(&start);
+    let mut queue = VecDeque::new();
+    let mut arena = Arena::new();
+    let mut seen = ZobristSet::default();
+    let mut stats = Stats {
+        total_opened: 1,
+        max_states: 1,
+    };
+    let goal = State::from(end);
+
+    if start == *end {
+        return Outcome::Solved(Solution {
+            stats,
+            start,
+            moves: vec![],
+        });
+    }
+    seen.insert(State::from(&start));
+    queue.push_back((State::from(&start), arena.root(), 0));
+
+    while let Some((current, node, depth)) = queue.pop_front() {
+        if limits.exceeded(stats.total_opened) {
+            return Outcome::Interrupted(stats);
+        }
+        if ticker.tick() {
+            progress.report(&Status::Frontier {
+                open: queue.len(),
+                closed: seen.len() - queue.len(),
+                min_f: depth,
+                rate: ticker.rate(),
+            });
+        }
+        let mut found = None;
+        context.neighbors(&current, |m, state, _| {
+            if found.is_some() || seen.contains(&state) {
+                return;
+            }
+            let child = arena.push(node, m);
+            if state == goal {
+                found = Some(child);
+            }
+            seen.insert(state.clone());
+            queue.push_back((state, child, depth + 1));
+            stats.total_opened += 1;
+        });
+        if let Some(node) = found {
+            return Outcome::Solved(Solution {
+                stats,
+                start,
+                moves: arena.moves(node),
+            });
+        }
+        stats.max_states = stats.max_states.max(seen.len());
+    }
+    Outcome::Unsolvable(stats)
+}
+
+pub struct BFS;
+
+impl Solver for BFS {
+    fn name(&self) -> &'static str {
+        "BFS"
+    }
+
+    fn solve(
+        &mut self,
+        start: Puzzle,
+        end: &Puzzle,
+        _: &mut dyn Heuristic,
+        limits: &Limits,
+        progress: &mut dyn Progress,
+    ) -> Outcome {
+        bfs(start, end, limits, progress)
+    }
+}
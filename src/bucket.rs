@@ -0,0 +1,184 @@
+use std::collections::VecDeque;
+
+// Which entry leaves first among the ones sharing the lowest f
+//
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TieBreak {
+    // lowest g first, closest to the start
+    LowG,
+    // highest g first, deepest and usually closest to the goal
+    HighG,
+    // first pushed first
+    Fifo,
+    // last pushed first
+    Lifo,
+}
+
+// One list per g, only the first one is used when g does not matter
+//
+struct Bucket<T> {
+    by_g: Vec<VecDeque<T>>,
+    // no entry has a lower g, the lists below are all empty
+    low: usize,
+    len: usize,
+}
+
+impl<T> Bucket<T> {
+    fn new() -> Bucket<T> {
+        Bucket {
+            by_g: vec![],
+            low: 0,
+            len: 0,
+        }
+    }
+}
+
+// Open list for small integer costs: one bucket per f value so pushing
+// and popping are O(1) amortized instead of O(log n) with a binary heap.
+// Inside the lowest bucket the tie-breaking policy picks the entry,
+// entries of the same g leave last in first out.
+//
+// [https://en.wikipedia.org/wiki/Bucket_queue]
+//
+pub struct BucketQueue<T> {
+    buckets: Vec<Bucket<T>>,
+    tie_break: TieBreak,
+    min: usize,
+    len: usize,
+}
+
+impl<T> BucketQueue<T> {
+    pub fn new(tie_break: TieBreak) -> BucketQueue<T> {
+        BucketQueue {
+            buckets: vec![],
+            tie_break,
+            min: 0,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn push(&mut self, f: u32, g: u32, item: T) {
+        let f = f as usize;
+        let g = match self.tie_break {
+            TieBreak::LowG | TieBreak::HighG => g as usize,
+            TieBreak::Fifo | TieBreak::Lifo => 0,
+        };
+        while self.buckets.len() <= f {
+            self.buckets.push(Bucket::new());
+        }
+        let bucket = &mut self.buckets[f];
+        while bucket.by_g.len() <= g {
+            bucket.by_g.push(VecDeque::new());
+        }
+        bucket.by_g[g].push_back(item);
+        bucket.low = bucket.low.min(g);
+        bucket.len += 1;
+        self.len += 1;
+        // the heuristic of a greedy search is not monotone, f can go down
+        self.min = self.min.min(f);
+    }
+
+    // The entry with the lowest f along with that f
+    //
+    pub fn pop(&mut self) -> Option<(u32, T)> {
+        if self.len == 0 {
+            return None;
+        }
+        while self.buckets[self.min].len == 0 {
+            self.min += 1;
+        }
+        let bucket = &mut self.buckets[self.min];
+        let item = match self.tie_break {
+            TieBreak::LowG => {
+                while bucket.by_g[bucket.low].is_empty() {
+                    bucket.low += 1;
+                }
+                bucket.by_g[bucket.low].pop_back()
+            }
+            TieBreak::HighG => {
+                while bucket.by_g.last().is_some_and(|l| l.is_empty()) {
+                    bucket.by_g.pop();
+                }
+                bucket.by_g.last_mut().and_then(|list| list.pop_back())
+            }
+            TieBreak::Fifo => bucket.by_g[0].pop_front(),
+            TieBreak::Lifo => bucket.by_g[0].pop_back(),
+        };
+        bucket.len -= 1;
+        self.len -= 1;
+        item.map(|item| (self.min as u32, item))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drain(tie_break: TieBreak) -> Vec<char> {
+        let mut queue = BucketQueue::new(tie_break);
+        queue.push(5, 1, 'a');
+        queue.push(5, 3, 'b');
+        queue.push(4, 0, 'c');
+        queue.push(5, 1, 'd');
+        queue.push(7, 0, 'e');
+        queue.push(5, 3, 'f');
+        let mut order = vec![];
+        while let Some((_, item)) = queue.pop() {
+            order.push(item);
+        }
+        assert!(queue.is_empty());
+        order
+    }
+
+    #[test]
+    fn test_tie_breaks() {
+        assert_eq!(
+            drain(TieBreak::LowG),
+            vec!['c', 'd', 'a', 'f', 'b', 'e']
+        );
+        assert_eq!(
+            drain(TieBreak::HighG),
+            vec!['c', 'f', 'b', 'd', 'a', 'e']
+        );
+        assert_eq!(
+            drain(TieBreak::Fifo),
+            vec!['c', 'a', 'b', 'd', 'f', 'e']
+        );
+        assert_eq!(
+            drain(TieBreak::Lifo),
+            vec!['c', 'f', 'd', 'b', 'a', 'e']
+        );
+    }
+
+    #[test]
+    fn test_lower_f_after_pop() {
+        let mut queue = BucketQueue::new(TieBreak::HighG);
+        queue.push(3, 0, 'a');
+        queue.push(6, 0, 'b');
+        assert_eq!(queue.pop(), Some((3, 'a')));
+        queue.push(2, 0, 'c');
+        assert_eq!(queue.pop(), Some((2, 'c')));
+        assert_eq!(queue.pop(), Some((6, 'b')));
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn test_lower_g_after_pop() {
+        let mut queue = BucketQueue::new(TieBreak::LowG);
+        queue.push(4, 2, 'a');
+        queue.push(4, 3, 'b');
+        assert_eq!(queue.pop(), Some((4, 'a')));
+        queue.push(4, 1, 'c');
+        assert_eq!(queue.pop(), Some((4, 'c')));
+        assert_eq!(queue.pop(), Some((4, 'b')));
+        assert_eq!(queue.pop(), None);
+    }
+}
@@ -0,0 +1,560 @@
+use super::heuristics::Heuristic;
+use super::moves::Move;
+use super::progress::Progress;
+use super::puzzle::Puzzle;
+use super::solution::Solution;
+use super::solver::{Limits, Outcome, Solver, Stats};
+use std::collections::{HashMap, VecDeque};
+
+// Part of the board still to solve, bounds included
+//
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rect {
+    pub top: usize,
+    pub bottom: usize,
+    pub left: usize,
+    pub right: usize,
+}
+
+impl Rect {
+    pub fn height(&self) -> usize {
+        self.bottom - self.top + 1
+    }
+
+    pub fn width(&self) -> usize {
+        self.right - self.left + 1
+    }
+
+    fn contains(&self, row: usize, column: usize) -> bool {
+        (self.top..=self.bottom).contains(&row)
+            && (self.left..=self.right).contains(&column)
+    }
+}
+
+// Where a line is placed from: local rows go away from the line being
+// placed and local columns along it, so the same code places the top,
+// bottom, left or right line of the rectangle
+//
+#[derive(Clone, Copy)]
+struct Frame {
+    origin: (isize, isize),
+    down: (isize, isize),
+    along: (isize, isize),
+}
+
+impl Frame {
+    fn global(&self, n: usize, row: usize, column: usize) -> usize {
+        let (r, c) = (row as isize, column as isize);
+        let gr = self.origin.0 + r * self.down.0 + c * self.along.0;
+        let gc = self.origin.1 + r * self.down.1 + c * self.along.1;
+        gr as usize * n + gc as usize
+    }
+
+    fn local(&self, n: usize, cell: usize) -> (usize, usize) {
+        let dr = (cell / n) as isize - self.origin.0;
+        let dc = (cell % n) as isize - self.origin.1;
+        let row = dr * self.down.0 + dc * self.down.1;
+        let column = dr * self.along.0 + dc * self.along.1;
+        (row as usize, column as usize)
+    }
+}
+
+// The move taking the blank from `from` to the neighbor cell `to`
+//
+fn direction(n: usize, from: usize, to: usize) -> Move {
+    if to + n == from {
+        Move::Up
+    } else if to == from + n {
+        Move::Down
+    } else if to + 1 == from {
+        Move::Left
+    } else {
+        Move::Right
+    }
+}
+
+pub struct Board {
+    pub n: usize,
+    pub flat: Vec<u16>,
+    // cell of every tile, the blank included
+    position: Vec<usize>,
+    locked: Vec<bool>,
+    pub moves: Vec<Move>,
+    pub opened: usize,
+    // scratch space of the blank searches
+    seen: Vec<u32>,
+    stamp: u32,
+    from: Vec<usize>,
+    queue: VecDeque<usize>,
+}
+
+impl Board {
+    pub fn new(puzzle: &Puzzle) -> Board {
+        let cells = puzzle.flat.len();
+        let mut position = vec![0; cells];
+        for (cell, &tile) in puzzle.flat.iter().enumerate() {
+            position[tile as usize] = cell;
+        }
+        Board {
+            n: puzzle.n,
+            flat: puzzle.flat.clone(),
+            position,
+            locked: vec![false; cells],
+            moves: vec![],
+            opened: 0,
+            seen: vec![0; cells],
+            stamp: 0,
+            from: vec![0; cells],
+            queue: VecDeque::new(),
+        }
+    }
+
+    fn blank(&self) -> usize {
+        self.position[0]
+    }
+
+    fn neighbors(&self, rect: &Rect, cell: usize) -> Vec<usize> {
+        let (row, column) = (cell / self.n, cell % self.n);
+        let mut neighbors = Vec::with_capacity(4);
+        if row > rect.top {
+            neighbors.push(cell - self.n);
+        }
+        if row < rect.bottom {
+            neighbors.push(cell + self.n);
+        }
+        if column > rect.left {
+            neighbors.push(cell - 1);
+        }
+        if column < rect.right {
+            neighbors.push(cell + 1);
+        }
+        neighbors
+    }
+
+    fn slide(&mut self, to: usize) {
+        let blank = self.blank();
+        let tile = self.flat[to];
+        self.moves.push(direction(self.n, blank, to));
+        self.flat.swap(blank, to);
+        self.position[tile as usize] = blank;
+        self.position[0] = to;
+    }
+
+    // Shortest way for the blank to one of the cells accepted by `target`
+    // without going through locked cells or `avoid`, false if there is none
+    //
+    fn walk_blank(
+        &mut self,
+        rect: &Rect,
+        avoid: Option<usize>,
+        target: impl Fn(usize) -> bool,
+    ) -> bool {
+        self.stamp += 1;
+        let start = self.blank();
+        self.seen[start] = self.stamp;
+        self.queue.clear();
+        self.queue.push_back(start);
+        let mut found = None;
+        while let Some(cell) = self.queue.pop_front() {
+            self.opened += 1;
+            if target(cell) {
+                found = Some(cell);
+                break;
+            }
+            for next in self.neighbors(rect, cell) {
+                if self.seen[next] == self.stamp
+                    || self.locked[next]
+                    || Some(next) == avoid
+                {
+                    continue;
+                }
+                self.seen[next] = self.stamp;
+                self.from[next] = cell;
+                self.queue.push_back(next);
+            }
+        }
+        let mut cell = match found {
+            Some(cell) => cell,
+            None => return false,
+        };
+        let mut path = vec![];
+        while cell != start {
+            path.push(cell);
+            cell = self.from[cell];
+        }
+        for &cell in path.iter().rev() {
+            self.slide(cell);
+        }
+        true
+    }
+
+    // Brings `tile` to a local cell, first along its local row then along
+    // its local column
+    //
+    fn move_tile(
+        &mut self,
+        rect: &Rect,
+        frame: &Frame,
+        tile: u16,
+        row: usize,
+        column: usize,
+    ) -> bool {
+        loop {
+            let at = self.position[tile as usize];
+            let (r, c) = frame.local(self.n, at);
+            let (r, c) = if c < column {
+                (r, c + 1)
+            } else if c > column {
+                (r, c - 1)
+            } else if r < row {
+                (r + 1, c)
+            } else if r > row {
+                (r - 1, c)
+            } else {
+                return true;
+            };
+            let next = frame.global(self.n, r, c);
+            if !self.walk_blank(rect, Some(at), |cell| cell == next) {
+                return false;
+            }
+            self.slide(at);
+        }
+    }
+
+    // Breadth-first search on the few tiles that matter inside a small
+    // box, the blank and every tile of `targets` must already be in it
+    //
+    fn solve_box(
+        &mut self,
+        area: &Rect,
+        targets: &[(u16, usize)],
+    ) -> bool {
+        let tracked = |board: &Board| {
+            let mut state = vec![board.blank()];
+            state.extend(
+                targets.iter().map(|&(t, _)| board.position[t as usize]),
+            );
+            state
+        };
+        let solved = |state: &[usize]| {
+            targets.iter().zip(&state[1..]).all(|(&(_, c), &p)| c == p)
+        };
+        let start = tracked(self);
+        let mut parents: HashMap<Vec<usize>, Option<Vec<usize>>> =
+            HashMap::new();
+        let mut queue = VecDeque::new();
+        parents.insert(start.clone(), None);
+        queue.push_back(start);
+        let mut found = None;
+        while let Some(state) = queue.pop_front() {
+            self.opened += 1;
+            if solved(&state) {
+                found = Some(state);
+                break;
+            }
+            for next in self.neighbors(area, state[0]) {
+                let mut child = state.clone();
+                for p in child[1..].iter_mut() {
+                    if *p == next {
+                        *p = state[0];
+                    }
+                }
+                child[0] = next;
+                if !parents.contains_key(&child) {
+                    parents.insert(child.clone(), Some(state.clone()));
+                    queue.push_back(child);
+                }
+            }
+        }
+        let mut state = match found {
+            Some(state) => state,
+            None => return false,
+        };
+        let mut path = vec![];
+        while let Some(Some(parent)) = parents.get(&state) {
+            path.push(state[0]);
+            state = parent.clone();
+        }
+        for &cell in path.iter().rev() {
+            self.slide(cell);
+        }
+        true
+    }
+
+    // Places the local row 0 of a frame, `length` tiles long with at least
+    // two more rows below it
+    //
+    fn place_line(
+        &mut self,
+        rect: &Rect,
+        frame: &Frame,
+        goal: &[u16],
+        length: usize,
+    ) -> bool {
+        let n = self.n;
+        let tile_at = |column| goal[frame.global(n, 0, column)];
+        for column in 0..length - 2 {
+            if !self.move_tile(rect, frame, tile_at(column), 0, column) {
+                return false;
+            }
+            self.locked[frame.global(n, 0, column)] = true;
+        }
+
+        // The last two go in together: the last one where the one before
+        // goes, the one before just under it, then a small search in the
+        // 3x2 box around them turns them into place
+        let (a, b) = (tile_at(length - 2), tile_at(length - 1));
+        let (left, right) = (length - 2, length - 1);
+        if !self.move_tile(rect, frame, b, 0, left) {
+            return false;
+        }
+        let corner = frame.global(n, 0, left);
+        self.locked[corner] = true;
+        let (row, column) = frame.local(n, self.position[a as usize]);
+        let trapped = column == right && row < 2;
+        if !trapped && !self.move_tile(rect, frame, a, 1, left) {
+            return false;
+        }
+        self.locked[corner] = false;
+
+        let ends = [frame.global(n, 0, left), frame.global(n, 2, right)];
+        let (rows, columns) = (ends.map(|c| c / n), ends.map(|c| c % n));
+        let area = Rect {
+            top: rows[0].min(rows[1]),
+            bottom: rows[0].max(rows[1]),
+            left: columns[0].min(columns[1]),
+            right: columns[0].max(columns[1]),
+        };
+        let (pa, pb) =
+            (self.position[a as usize], self.position[b as usize]);
+        let inside = |cell: usize| {
+            area.contains(cell / n, cell % n) && cell != pa && cell != pb
+        };
+        if !self.walk_blank_around(rect, pa, pb, inside) {
+            return false;
+        }
+        let targets = [
+            (a, frame.global(n, 0, left)),
+            (b, frame.global(n, 0, right)),
+        ];
+        if !self.solve_box(&area, &targets) {
+            return false;
+        }
+        for column in 0..length {
+            self.locked[frame.global(n, 0, column)] = true;
+        }
+        true
+    }
+
+    // Same as walk_blank but two cells are out of the way
+    //
+    fn walk_blank_around(
+        &mut self,
+        rect: &Rect,
+        first: usize,
+        second: usize,
+        target: impl Fn(usize) -> bool,
+    ) -> bool {
+        let was_locked = (self.locked[first], self.locked[second]);
+        self.locked[first] = true;
+        self.locked[second] = true;
+        let found = self.walk_blank(rect, None, target);
+        self.locked[first] = was_locked.0;
+        self.locked[second] = was_locked.1;
+        found
+    }
+
+    // Peels lines off the rectangle, always keeping the cell of the blank
+    // in the goal inside, until both sides are at most `until` long.
+    // Returns what is left, None when a line could not be placed.
+    //
+    pub fn reduce(
+        &mut self,
+        goal: &Puzzle,
+        until: usize,
+        limits: &Limits,
+    ) -> Option<Rect> {
+        let n = self.n;
+        let (blank_row, blank_column) = (goal.blank / n, goal.blank % n);
+        let mut rect = Rect {
+            top: 0,
+            bottom: n - 1,
+            left: 0,
+            right: n - 1,
+        };
+        let until = until.max(2);
+        while rect.height() > until || rect.width() > until {
+            if limits.exceeded(self.opened) {
+                return None;
+            }
+            let whole = rect;
+            let (height, width) = (rect.height(), rect.width());
+            let (frame, length) = if height >= width {
+                let length = width;
+                let (top, left) = (rect.top as isize, rect.left as isize);
+                if blank_row != rect.top {
+                    rect.top += 1;
+                    (frame((top, left), (1, 0), (0, 1)), length)
+                } else {
+                    let bottom = rect.bottom as isize;
+                    rect.bottom -= 1;
+                    (frame((bottom, left), (-1, 0), (0, 1)), length)
+                }
+            } else {
+                let length = height;
+                let top = rect.top as isize;
+                if blank_column != rect.left {
+                    let left = rect.left as isize;
+                    rect.left += 1;
+                    (frame((top, left), (0, 1), (1, 0)), length)
+                } else {
+                    let right = rect.right as isize;
+                    rect.right -= 1;
+                    (frame((top, right), (0, -1), (1, 0)), length)
+                }
+            };
+            if !self.place_line(&whole, &frame, &goal.flat, length) {
+                return None;
+            }
+        }
+        Some(rect)
+    }
+
+    // The square left by `reduce` as a board of its own, with the tiles
+    // numbered in the order they have in the goal. Its moves are the
+    // moves of the whole board.
+    //
+    pub fn sub_puzzle(
+        &self,
+        goal: &Puzzle,
+        rect: &Rect,
+    ) -> (Puzzle, Puzzle) {
+        let cells = (rect.top..=rect.bottom)
+            .flat_map(|row| {
+                (rect.left..=rect.right)
+                    .map(move |column| row * self.n + column)
+            })
+            .collect::<Vec<_>>();
+        let mut labels = vec![0; self.flat.len()];
+        let mut next = 1;
+        for &cell in &cells {
+            if goal.flat[cell] != 0 {
+                labels[goal.flat[cell] as usize] = next;
+                next += 1;
+            }
+        }
+        let relabel = |flat: &[u16]| {
+            cells
+                .iter()
+                .map(|&cell| labels[flat[cell] as usize])
+                .collect::<Vec<_>>()
+        };
+        let size = rect.width();
+        let sub_goal = Puzzle::from_flat(size, relabel(&goal.flat));
+        let mut sub_start = Puzzle::from_flat(size, relabel(&self.flat));
+        sub_start.set_goal(&sub_goal);
+        (sub_start, sub_goal)
+    }
+
+    // Every tile of a small rectangle into place at once
+    //
+    pub fn finish(&mut self, goal: &Puzzle, rect: &Rect) -> bool {
+        let mut targets = vec![];
+        for row in rect.top..=rect.bottom {
+            for column in rect.left..=rect.right {
+                let cell = row * self.n + column;
+                if goal.flat[cell] != 0 {
+                    targets.push((goal.flat[cell], cell));
+                }
+            }
+        }
+        self.solve_box(rect, &targets)
+    }
+}
+
+fn frame(
+    origin: (isize, isize),
+    down: (isize, isize),
+    along: (isize, isize),
+) -> Frame {
+    Frame {
+        origin,
+        down,
+        along,
+    }
+}
+
+// Solves the board the way people do: place the top row, then the left
+// column, and so on until a 2x2 square is left. Lines are peeled from the
+// side away from where the goal wants the blank, so any goal works. Far
+// from the shortest solution but polynomial, a 100x100 board takes
+// seconds.
+//
+pub fn constructive(
+    start: Puzzle,
+    end: &Puzzle,
+    limits: &Limits,
+) -> Outcome {
+    let mut board = Board::new(&start);
+    let rect = board.reduce(end, 2, limits);
+    let stats = |board: &Board| Stats {
+        total_opened: board.opened,
+        max_states: board.flat.len(),
+    };
+    if limits.exceeded(board.opened) {
+        return Outcome::Interrupted(stats(&board));
+    }
+    match rect {
+        Some(rect) if board.finish(end, &rect) => {
+            Outcome::Solved(Solution {
+                stats: stats(&board),
+                start,
+                moves: board.moves,
+            })
+        }
+        _ => Outcome::Unsolvable(stats(&board)),
+    }
+}
+
+pub struct Constructive;
+
+impl Solver for Constructive {
+    fn name(&self) -> &'static str {
+        "Constructive"
+    }
+
+    fn solve(
+        &mut self,
+        start: Puzzle,
+        end: &Puzzle,
+        _: &mut dyn Heuristic,
+        limits: &Limits,
+        _: &mut dyn Progress,
+    ) -> Outcome {
+        constructive(start, end, limits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::{Difficulty, PuzzleKind};
+
+    #[test]
+    fn test_every_goal() {
+        for kind in
+            [PuzzleKind::Classic, PuzzleKind::Snail, PuzzleKind::Reverse]
+        {
+            for n in 2..12 {
+                let goal = Puzzle::new(kind, n);
+                let start =
+                    Puzzle::new_randomized(kind, Difficulty::Hard, n);
+                let solution =
+                    constructive(start, &goal, &Limits::default())
+                        .solution()
+                        .unwrap();
+                assert_eq!(solution.end(), goal, "{:?} {}", kind, n);
+            }
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use super::heuristics::Heuristic;
+use super::ida_star::ida_star;
+use super::moves::Move;
+use super::progress::Progress;
+use super::puzzle::Puzzle;
+use super::solver::{Limits, Outcome, Stats};
+use super::zobrist::ZobristMap;
+
+// Every optimal solution of a board: their length, how many there are and,
+// unless they are only counted, their moves
+//
+pub struct Optimal {
+    pub length: usize,
+    pub count: u64,
+    pub solutions: Vec<Vec<Move>>,
+    pub stats: Stats,
+}
+
+// IDA* finds the optimal length, then a depth-first search bounded by it
+// goes down every path its last iteration could have taken. A shorter path
+// to a board would give a shorter solution, so no optimal solution goes
+// through the same board twice and every path found is a distinct one.
+//
+// When only counting, the number of paths from a board with so many moves
+// left is kept, boards reached again are not searched twice.
+//
+#[allow(clippy::result_large_err)]
+pub fn optimal_solutions(
+    start: Puzzle,
+    end: &Puzzle,
+    h: &mut dyn Heuristic,
+    count_only: bool,
+    limits: &Limits,
+    progress: &mut dyn Progress,
+) -> Result<Optimal, Outcome> {
+    let found = ida_star(start.clone(), end, h, None, limits, progress);
+    let solution = match found {
+        Outcome::Solved(solution) => solution,
+        outcome => return Err(outcome),
+    };
+    let length = solution.moves.len();
+    let mut walk = Walk {
+        end,
+        h,
+        limits,
+        count_only,
+        counts: ZobristMap::default(),
+        moves: Vec::with_capacity(length),
+        solutions: vec![],
+        opened: solution.stats.total_opened,
+    };
+    let start_h = walk.h.first_time(&start, end);
+    let count = walk.count(&start, start_h, length as u32);
+    let stats = Stats {
+        total_opened: walk.opened,
+        max_states: walk.counts.len().max(length + 1),
+    };
+    match count {
+        Some(count) => Ok(Optimal {
+            length,
+            count,
+            solutions: walk.solutions,
+            stats,
+        }),
+        None => Err(Outcome::Interrupted(stats)),
+    }
+}
+
+struct Walk<'a> {
+    end: &'a Puzzle,
+    h: &'a mut dyn Heuristic,
+    limits: &'a Limits,
+    count_only: bool,
+    // paths to the goal from a board, by zobrist key and moves left
+    counts: ZobristMap<(u64, u32), u64>,
+    moves: Vec<Move>,
+    solutions: Vec<Vec<Move>>,
+    opened: usize,
+}
+
+impl Walk<'_> {
+    // Paths to the goal from `puzzle` in exactly `left` moves, none when
+    // the limits were hit
+    //
+    fn count(
+        &mut self,
+        puzzle: &Puzzle,
+        h: u32,
+        left: u32,
+    ) -> Option<u64> {
+        self.opened += 1;
+        if self.limits.exceeded(self.opened) {
+            return None;
+        }
+        if h > left {
+            return Some(0);
+        }
+        if left == 0 {
+            if !puzzle.is_goal(self.end) {
+                return Some(0);
+            }
+            if !self.count_only {
+                self.solutions.push(self.moves.clone());
+            }
+            return Some(1);
+        }
+        if self.count_only {
+            if let Some(&count) = self.counts.get(&(puzzle.key, left)) {
+                return Some(count);
+            }
+        }
+        let mut count = 0;
+        for m in Move::moves(puzzle) {
+            let mut p = puzzle.clone();
+            m.apply(&mut p);
+            let h = self.h.update(h, &p, self.end);
+            self.moves.push(m);
+            count += self.count(&p, h, left - 1)?;
+            self.moves.pop();
+        }
+        if self.count_only {
+            self.counts.insert((puzzle.key, left), count);
+        }
+        Some(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::subject_1;
+    use crate::heuristics::{get_heuristic, HeuristicFunc};
+    use crate::progress::Silent;
+    use std::collections::HashMap;
+
+    // Shortest paths counted breadth-first, layer after layer
+    //
+    fn breadth_first_count(start: &Puzzle, end: &Puzzle) -> (usize, u64) {
+        let mut seen = HashMap::new();
+        seen.insert(start.flat.clone(), 0);
+        let mut layer = HashMap::new();
+        layer.insert(start.flat.clone(), (start.clone(), 1));
+        for depth in 1.. {
+            let mut next: HashMap<Vec<u16>, (Puzzle, u64)> =
+                HashMap::new();
+            for (board, paths) in layer.values() {
+                let mut board = board.clone();
+                board.was = Move::Hold;
+                for neighbor in board.neighbors() {
+                    if seen.contains_key(&neighbor.flat) {
+                        continue;
+                    }
+                    next.entry(neighbor.flat.clone())
+                        .or_insert((neighbor, 0))
+                        .1 += paths;
+                }
+            }
+            if let Some((_, paths)) = next.get(&end.flat) {
+                return (depth, *paths);
+            }
+            for flat in next.keys() {
+                seen.insert(flat.clone(), depth);
+            }
+            layer = next;
+        }
+        unreachable!()
+    }
+
+    #[test]
+    fn test_every_optimal_solution() {
+        let (start, goal) = subject_1();
+        let mut h = get_heuristic(HeuristicFunc::LinearConflicts);
+        let mut search = |count_only| {
+            optimal_solutions(
+                start.clone(),
+                &goal,
+                h.as_mut(),
+                count_only,
+                &Limits::default(),
+                &mut Silent,
+            )
+            .ok()
+            .unwrap()
+        };
+
+        let all = search(false);
+        let counted = search(true);
+        let (length, paths) = breadth_first_count(&start, &goal);
+
+        assert_eq!((all.length, all.count), (length, paths));
+        assert_eq!((counted.length, counted.count), (length, paths));
+        assert!(counted.solutions.is_empty());
+        assert_eq!(all.solutions.len() as u64, paths);
+        for moves in &all.solutions {
+            let mut board = start.clone();
+            for m in moves {
+                m.apply(&mut board);
+            }
+            assert_eq!(board, goal);
+        }
+        let mut distinct = all.solutions.clone();
+        distinct.sort_by_key(|moves| format!("{:?}", moves));
+        distinct.dedup();
+        assert_eq!(distinct.len(), all.solutions.len());
+    }
+}
@@ -0,0 +1,28 @@
+use super::puzzle::{Matrix, Puzzle, PuzzleKind};
+
+// Boards the tests share, each one is returned as (start, goal) with the
+// goal already set on the start
+//
+fn with_goal(start: Matrix, goal: Puzzle) -> (Puzzle, Puzzle) {
+    let mut start = Puzzle::from_matrix(3, start);
+    start.set_goal(&goal);
+    (start, goal)
+}
+
+// puzzles/subject-1.txt, 15 moves from the snail goal
+//
+pub fn subject_1() -> (Puzzle, Puzzle) {
+    with_goal(
+        vec![vec![3, 2, 6], vec![1, 4, 0], vec![8, 7, 5]],
+        Puzzle::new(PuzzleKind::Snail, 3),
+    )
+}
+
+// One of the two 3x3 boards farthest from the classic goal, 31 moves
+//
+pub fn hardest_classic() -> (Puzzle, Puzzle) {
+    with_goal(
+        vec![vec![8, 6, 7], vec![2, 5, 4], vec![3, 0, 1]],
+        Puzzle::new(PuzzleKind::Classic, 3),
+    )
+}
@@ -12,24 +12,15 @@ fn generate_classic(n: usize) -> Vec<u16> {
 
 pub fn new_classic(n: usize) -> Puzzle {
     let mut flat = generate_classic(n);
-    let end = vec![0; flat.len()];
     let blank = flat.len() - 1;
     flat[blank] = 0;
-    Puzzle {
-        n,
-        flat,
-        end,
-        blank,
-        was: Move::Hold,
-    }
+    Puzzle::from_flat(n, flat)
 }
 
 pub fn new_reverse(n: usize) -> Puzzle {
-    let mut p = new_classic(n);
-    p.flat.reverse();
-
-    p.blank = Puzzle::get_index_of(&p.flat, 0);
-    p
+    let mut flat = new_classic(n).flat;
+    flat.reverse();
+    Puzzle::from_flat(n, flat)
 }
 
 // [https://www.geeksforgeeks.org/print-a-given-matrix-in-spiral-form/]
@@ -68,16 +59,9 @@ fn generate_snail(n: usize) -> Vec<u16> {
 
 pub fn new_snail(n: usize) -> Puzzle {
     let mut flat = generate_snail(n);
-    let end = vec![0; flat.len()];
     let blank = Puzzle::get_index_of(&flat, (n * n) as u16);
     flat[blank] = 0;
-    Puzzle {
-        n,
-        flat,
-        end,
-        blank,
-        was: Move::Hold,
-    }
+    Puzzle::from_flat(n, flat)
 }
 
 use rand::Rng;
@@ -95,6 +79,7 @@ pub fn randomize(puzzle: &mut Puzzle, iterations: usize) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::Arc;
 
     #[test]
     fn test_new_snail() {
@@ -103,9 +88,10 @@ mod tests {
             Puzzle {
                 n: 3,
                 flat: vec![1, 2, 3, 8, 0, 4, 7, 6, 5],
-                end: vec![],
+                end: Arc::default(),
                 blank: 4,
                 was: Move::Hold,
+                key: 0,
             }
         );
 
@@ -116,9 +102,10 @@ mod tests {
                 flat: vec![
                     1, 2, 3, 4, 12, 13, 14, 5, 11, 0, 15, 6, 10, 9, 8, 7
                 ],
-                end: vec![],
+                end: Arc::default(),
                 blank: 9,
                 was: Move::Hold,
+                key: 0,
             }
         );
 
@@ -130,9 +117,10 @@ mod tests {
                     1, 2, 3, 4, 5, 16, 17, 18, 19, 6, 15, 24, 0, 20, 7,
                     14, 23, 22, 21, 8, 13, 12, 11, 10, 9
                 ],
-                end: vec![],
+                end: Arc::default(),
                 blank: 12,
                 was: Move::Hold,
+                key: 0,
             }
         );
     }
@@ -0,0 +1,258 @@
+use super::puzzle::Puzzle;
+use std::error::Error;
+
+// Above this many cells the table of every tile in every cell would take
+// too much memory, distances are computed on the fly instead
+//
+const MAX_TABLE_CELLS: usize = 1024;
+
+// A pattern of the board, None for a free cell
+//
+pub type Pattern = Vec<Option<u16>>;
+
+// Every board a search may end on: any of the patterns, a free cell takes
+// any tile. Tiles missing from a pattern, the blank included, may end
+// anywhere.
+//
+#[derive(Clone, Debug, PartialEq)]
+pub struct GoalSpec {
+    pub n: usize,
+    pub patterns: Vec<Pattern>,
+}
+
+impl GoalSpec {
+    pub fn new(
+        n: usize,
+        patterns: Vec<Pattern>,
+    ) -> Result<GoalSpec, Box<dyn Error>> {
+        let len = n * n;
+        if patterns.is_empty() {
+            return Err("The goal has no pattern !".into());
+        }
+        for pattern in &patterns {
+            if pattern.len() != len {
+                return Err("A goal pattern has the wrong size !".into());
+            }
+            let mut seen = vec![false; len];
+            for &t in pattern.iter().flatten() {
+                if t as usize >= len || seen[t as usize] {
+                    return Err("A goal pattern has a wrong tile !".into());
+                }
+                seen[t as usize] = true;
+            }
+        }
+        Ok(GoalSpec { n, patterns })
+    }
+
+    pub fn exact(goal: &Puzzle) -> GoalSpec {
+        GoalSpec {
+            n: goal.n,
+            patterns: vec![goal.flat.iter().map(|&t| Some(t)).collect()],
+        }
+    }
+
+    // A board matching the first pattern, the missing tiles in order on
+    // its free cells. Searches that need one end board get this one.
+    //
+    pub fn representative(&self) -> Puzzle {
+        Puzzle::from_flat(self.n, GoalSpec::fill(&self.patterns[0]))
+    }
+
+    pub fn fill(pattern: &[Option<u16>]) -> Vec<u16> {
+        let mut missing = GoalSpec::missing(pattern).into_iter();
+        pattern
+            .iter()
+            .map(|t| t.unwrap_or_else(|| missing.next().unwrap()))
+            .collect()
+    }
+
+    // The tiles free to end anywhere, in order
+    //
+    pub fn missing(pattern: &[Option<u16>]) -> Vec<u16> {
+        let mut placed = vec![false; pattern.len()];
+        for &t in pattern.iter().flatten() {
+            placed[t as usize] = true;
+        }
+        (0..pattern.len() as u16)
+            .filter(|&t| !placed[t as usize])
+            .collect()
+    }
+}
+
+// Where the tiles of one pattern have to go, None for the free ones. The
+// Manhattan distance of each tile from each cell is computed once here,
+// indexed by tile then cell, so heuristics only do lookups.
+//
+#[derive(Debug)]
+pub struct Layout {
+    n: usize,
+    cells: Vec<Option<usize>>,
+    manhattan: Vec<u8>,
+}
+
+impl Layout {
+    fn new(n: usize, pattern: &[Option<u16>]) -> Layout {
+        let len = pattern.len();
+        let mut cells = vec![None; len];
+        for (i, t) in pattern.iter().enumerate() {
+            if let Some(t) = t {
+                cells[*t as usize] = Some(i);
+            }
+        }
+        let mut manhattan = vec![];
+        if len <= MAX_TABLE_CELLS {
+            manhattan = vec![0; len * len];
+            // the blank and the free tiles stay at 0, they are not counted
+            for t in 1..len {
+                if let Some(cell) = cells[t] {
+                    for i in 0..len {
+                        manhattan[t * len + i] =
+                            Layout::distance(n, i, cell) as u8;
+                    }
+                }
+            }
+        }
+        Layout {
+            n,
+            cells,
+            manhattan,
+        }
+    }
+
+    fn distance(n: usize, i: usize, j: usize) -> u32 {
+        ((i % n).abs_diff(j % n) + (i / n).abs_diff(j / n)) as u32
+    }
+
+    // How many moves `tile` is away from its place when it is in `cell`
+    //
+    pub fn manhattan(&self, tile: u16, cell: usize) -> u32 {
+        let len = self.cells.len();
+        if !self.manhattan.is_empty() {
+            return self.manhattan[tile as usize * len + cell] as u32;
+        }
+        match self.cells[tile as usize] {
+            Some(goal) if tile != 0 => {
+                Layout::distance(self.n, cell, goal)
+            }
+            _ => 0,
+        }
+    }
+
+    // The cell of a tile in the goal, none when it is free
+    //
+    pub fn cell(&self, tile: u16) -> Option<usize> {
+        self.cells[tile as usize]
+    }
+
+    fn matches(&self, flat: &[u16]) -> bool {
+        self.cells
+            .iter()
+            .enumerate()
+            .all(|(t, cell)| cell.is_none_or(|c| flat[c] as usize == t))
+    }
+}
+
+// The layouts of a goal spec, shared by all the boards of a search.
+// Heuristics take the lowest value over the layouts.
+//
+#[derive(Debug, Default)]
+pub struct Goal {
+    layouts: Vec<Layout>,
+    exact: bool,
+}
+
+impl Goal {
+    pub fn new(goal: &Puzzle) -> Goal {
+        Goal::from_spec(&GoalSpec::exact(goal))
+    }
+
+    pub fn from_spec(spec: &GoalSpec) -> Goal {
+        let exact = spec.patterns.len() == 1
+            && spec.patterns[0].iter().all(Option::is_some);
+        Goal {
+            layouts: spec
+                .patterns
+                .iter()
+                .map(|pattern| Layout::new(spec.n, pattern))
+                .collect(),
+            exact,
+        }
+    }
+
+    pub fn layouts(&self) -> &[Layout] {
+        &self.layouts
+    }
+
+    // The only layout, none when there are several
+    //
+    pub fn single(&self) -> Option<&Layout> {
+        match self.layouts.as_slice() {
+            [layout] => Some(layout),
+            _ => None,
+        }
+    }
+
+    // Whether the goal is one whole board, which searches compare with
+    // directly. A board without a goal set counts as exact too.
+    //
+    pub fn is_exact(&self) -> bool {
+        self.exact || self.layouts.is_empty()
+    }
+
+    pub fn matches(&self, flat: &[u16]) -> bool {
+        self.layouts.iter().any(|layout| layout.matches(flat))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::PuzzleKind;
+
+    #[test]
+    fn test_table_matches_distance() {
+        for n in [3, 4, 33] {
+            let snail = Puzzle::new(PuzzleKind::Snail, n);
+            let goal = Goal::new(&snail);
+            let layout = goal.single().unwrap();
+            let len = n * n;
+
+            assert_eq!(layout.manhattan.is_empty(), len > MAX_TABLE_CELLS);
+            for t in 0..len as u16 {
+                assert_eq!(
+                    layout.manhattan(t, layout.cell(t).unwrap()),
+                    0
+                );
+            }
+            for i in 0..len {
+                let t = snail.flat[(i + n + 1) % len];
+                let expected = match t {
+                    0 => 0,
+                    _ => Layout::distance(n, i, layout.cell(t).unwrap()),
+                };
+                assert_eq!(layout.manhattan(t, i), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_partial_and_multiple_patterns() {
+        let mut top_row: Pattern = vec![None; 9];
+        top_row[..3].copy_from_slice(&[Some(1), Some(2), Some(3)]);
+        let mut corner: Pattern = vec![None; 9];
+        corner[8] = Some(0);
+        let spec = GoalSpec::new(3, vec![top_row, corner]).unwrap();
+        let goal = Goal::from_spec(&spec);
+
+        assert!(!goal.is_exact());
+        assert!(goal.matches(&[1, 2, 3, 0, 4, 5, 6, 7, 8]));
+        assert!(goal.matches(&[3, 2, 1, 4, 5, 6, 7, 8, 0]));
+        assert!(!goal.matches(&[2, 1, 3, 0, 4, 5, 6, 7, 8]));
+        assert_eq!(goal.layouts()[0].manhattan(4, 0), 0);
+        assert_eq!(
+            spec.representative().flat,
+            [1, 2, 3, 0, 4, 5, 6, 7, 8]
+        );
+        assert!(GoalSpec::new(3, vec![vec![Some(9); 9]]).is_err());
+    }
+}
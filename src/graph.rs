@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct LinearConflictGraph {
     pub conflicts: HashMap<u16, Vec<u16>>,
 }
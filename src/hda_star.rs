@@ -0,0 +1,378 @@
+use super::bucket::{BucketQueue, TieBreak};
+use super::heuristics::Heuristic;
+use super::moves::Move;
+use super::progress::{Progress, Status, REPORT_INTERVAL};
+use super::puzzle::Puzzle;
+use super::solution::Solution;
+use super::solver::{Limits, Outcome, Solver, Stats};
+use super::state::State;
+use super::zobrist::ZobristMap;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const TRANSITION_COST: u32 = 1;
+
+// How long an idle worker waits for nodes before looking at the others
+//
+const IDLE_WAIT: Duration = Duration::from_millis(1);
+
+// A generated board on its way to the thread that owns it
+//
+struct Message {
+    puzzle: Puzzle,
+    g: u32,
+    h: u32,
+    parent: Option<(State, Move)>,
+}
+
+// Best g seen for a board and how it was reached
+//
+type Closed = ZobristMap<State, (u32, Option<(State, Move)>)>;
+
+// What all the workers share
+//
+struct Shared<'a> {
+    end: &'a Puzzle,
+    limits: &'a Limits,
+    // cost of the best solution found so far
+    incumbent: AtomicU32,
+    goal: Mutex<Option<(u32, State)>>,
+    // workers with something to do plus messages not yet received, the
+    // search is over when it drops to zero. Every worker starts busy and
+    // the start board is the first message.
+    work: AtomicUsize,
+    done: AtomicBool,
+    interrupted: AtomicBool,
+    opened: AtomicUsize,
+    expanded: AtomicUsize,
+    last_f: AtomicU32,
+}
+
+// [https://www.aaai.org/Papers/ICAPS/2009/ICAPS09-005.pdf]
+//
+// Hash distributed A*: every board belongs to one thread, chosen by its
+// zobrist key. Each thread runs A* on the boards it owns and sends the
+// ones it generates to their owner. A thread finding the goal only gives
+// an incumbent, the others keep going until nothing left is cheaper.
+//
+pub fn hda_star(
+    start: Puzzle,
+    end: &Puzzle,
+    h: &mut dyn Heuristic,
+    threads: usize,
+    limits: &Limits,
+    progress: &mut dyn Progress,
+) -> Outcome {
+    let threads = threads.max(1);
+    let shared = Shared {
+        end,
+        limits,
+        incumbent: AtomicU32::new(u32::MAX),
+        goal: Mutex::new(None),
+        work: AtomicUsize::new(threads + 1),
+        done: AtomicBool::new(false),
+        interrupted: AtomicBool::new(false),
+        opened: AtomicUsize::new(1),
+        expanded: AtomicUsize::new(0),
+        last_f: AtomicU32::new(0),
+    };
+    let (senders, receivers): (Vec<_>, Vec<_>) =
+        (0..threads).map(|_| mpsc::channel()).unzip();
+
+    let start_h = h.first_time(&start, end);
+    let owner = (start.key % threads as u64) as usize;
+    let _ = senders[owner].send(Message {
+        puzzle: start.clone(),
+        g: 0,
+        h: start_h,
+        parent: None,
+    });
+
+    let results = thread::scope(|scope| {
+        let workers = receivers
+            .into_iter()
+            .enumerate()
+            .map(|(id, inbox)| {
+                let worker = Worker {
+                    id,
+                    shared: &shared,
+                    h: h.fork(),
+                    inbox,
+                    outboxes: senders.clone(),
+                    open: BucketQueue::new(TieBreak::HighG),
+                    closed: Closed::default(),
+                    max_states: 0,
+                };
+                scope.spawn(move || worker.run())
+            })
+            .collect::<Vec<_>>();
+
+        let started = Instant::now();
+        let mut last = started;
+        while !shared.done.load(Ordering::Acquire) {
+            thread::sleep(IDLE_WAIT * 10);
+            if last.elapsed() < REPORT_INTERVAL {
+                continue;
+            }
+            last = Instant::now();
+            let opened = shared.opened.load(Ordering::Relaxed);
+            let expanded = shared.expanded.load(Ordering::Relaxed);
+            progress.report(&Status::Frontier {
+                open: opened.saturating_sub(expanded),
+                closed: expanded,
+                min_f: shared.last_f.load(Ordering::Relaxed),
+                rate: expanded as f64 / started.elapsed().as_secs_f64(),
+            });
+        }
+        workers
+            .into_iter()
+            .map(|w| w.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+
+    let stats = Stats {
+        total_opened: shared.opened.load(Ordering::Relaxed),
+        max_states: results.iter().map(|(_, max)| max).sum(),
+    };
+    if shared.interrupted.load(Ordering::Relaxed) {
+        return Outcome::Interrupted(stats);
+    }
+    let goal = shared.goal.lock().unwrap().take();
+    match goal {
+        Some((_, goal)) => {
+            let closed =
+                results.iter().map(|(c, _)| c).collect::<Vec<_>>();
+            Outcome::Solved(Solution {
+                stats,
+                start,
+                moves: moves_to(&closed, goal),
+            })
+        }
+        None => Outcome::Unsolvable(stats),
+    }
+}
+
+// Walks the parents back from the goal, through the thread each board
+// belongs to
+//
+fn moves_to(closed: &[&Closed], goal: State) -> Vec<Move> {
+    let mut moves = vec![];
+    let mut current = goal;
+    loop {
+        let parent = closed
+            .iter()
+            .find_map(|c| c.get(&current))
+            .and_then(|(_, parent)| parent.clone());
+        match parent {
+            Some((state, m)) => {
+                moves.push(m);
+                current = state;
+            }
+            None => break,
+        }
+    }
+    moves.reverse();
+    moves
+}
+
+struct Worker<'a> {
+    id: usize,
+    shared: &'a Shared<'a>,
+    h: Box<dyn Heuristic + Send>,
+    inbox: Receiver<Message>,
+    outboxes: Vec<Sender<Message>>,
+    open: BucketQueue<(Puzzle, u32, u32)>,
+    closed: Closed,
+    max_states: usize,
+}
+
+impl Worker<'_> {
+    fn run(mut self) -> (Closed, usize) {
+        let shared = self.shared;
+        let mut busy = true;
+        while !shared.done.load(Ordering::Acquire) {
+            while let Ok(message) = self.inbox.try_recv() {
+                self.receive(message, busy);
+                busy = true;
+            }
+            if self.expand_next() {
+                continue;
+            }
+            if busy {
+                busy = false;
+                self.idle();
+            }
+            match self.inbox.recv_timeout(IDLE_WAIT) {
+                Ok(message) => {
+                    self.receive(message, false);
+                    busy = true;
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    if shared.work.load(Ordering::Acquire) == 0 {
+                        shared.done.store(true, Ordering::Release);
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+        (self.closed, self.max_states)
+    }
+
+    fn idle(&self) {
+        if self.shared.work.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.shared.done.store(true, Ordering::Release);
+        }
+    }
+
+    // A message received while busy is one unit of work less, received
+    // while idle it is the work of the thread from now on
+    //
+    fn receive(&mut self, message: Message, counted: bool) {
+        if counted {
+            self.shared.work.fetch_sub(1, Ordering::AcqRel);
+        }
+        let state = State::from(&message.puzzle);
+        let better = match self.closed.get(&state) {
+            Some(&(g, _)) => message.g < g,
+            None => true,
+        };
+        let f = message.g + message.h;
+        if !better || f >= self.shared.incumbent.load(Ordering::Acquire) {
+            return;
+        }
+        self.closed.insert(state, (message.g, message.parent));
+        self.open.push(
+            f,
+            message.g,
+            (message.puzzle, message.g, message.h),
+        );
+        // boards still open are in the closed map too
+        self.max_states = self.max_states.max(self.closed.len());
+    }
+
+    // Expands the best board of the thread, false once nothing left can
+    // beat the incumbent
+    //
+    fn expand_next(&mut self) -> bool {
+        let shared = self.shared;
+        let (f, (puzzle, g, h)) = match self.open.pop() {
+            Some(entry) => entry,
+            None => return false,
+        };
+        let incumbent = shared.incumbent.load(Ordering::Acquire);
+        if f >= incumbent {
+            // every other board of the open list is at least as far
+            while self.open.pop().is_some() {}
+            return false;
+        }
+        let state = State::from(&puzzle);
+        if self.closed.get(&state).is_some_and(|&(best, _)| best < g) {
+            return true;
+        }
+        if puzzle == *shared.end {
+            shared.incumbent.fetch_min(g, Ordering::AcqRel);
+            let mut goal = shared.goal.lock().unwrap();
+            if goal.as_ref().is_none_or(|&(best, _)| g < best) {
+                *goal = Some((g, state));
+            }
+            return true;
+        }
+        let expanded = shared.expanded.fetch_add(1, Ordering::Relaxed);
+        if shared
+            .limits
+            .exceeded(shared.opened.load(Ordering::Relaxed))
+        {
+            shared.interrupted.store(true, Ordering::Release);
+            shared.done.store(true, Ordering::Release);
+            return false;
+        }
+        if expanded % 1024 == 0 {
+            shared.last_f.store(f, Ordering::Relaxed);
+        }
+
+        let g = g + TRANSITION_COST;
+        for neighbor in puzzle.neighbors() {
+            let h = self.h.update(h, &neighbor, shared.end);
+            if g + h >= shared.incumbent.load(Ordering::Acquire) {
+                continue;
+            }
+            let owner =
+                (neighbor.key % self.outboxes.len() as u64) as usize;
+            let message = Message {
+                parent: Some((state.clone(), neighbor.was.opposite())),
+                puzzle: neighbor,
+                g,
+                h,
+            };
+            shared.opened.fetch_add(1, Ordering::Relaxed);
+            if owner == self.id {
+                self.receive(message, false);
+            } else {
+                shared.work.fetch_add(1, Ordering::AcqRel);
+                if self.outboxes[owner].send(message).is_err() {
+                    shared.work.fetch_sub(1, Ordering::AcqRel);
+                }
+            }
+        }
+        true
+    }
+}
+
+pub struct HDAStar {
+    pub threads: usize,
+}
+
+impl Solver for HDAStar {
+    fn name(&self) -> &'static str {
+        "HDA*"
+    }
+
+    fn solve(
+        &mut self,
+        start: Puzzle,
+        end: &Puzzle,
+        h: &mut dyn Heuristic,
+        limits: &Limits,
+        progress: &mut dyn Progress,
+    ) -> Outcome {
+        hda_star(start, end, h, self.threads, limits, progress)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::a_star::a_star;
+    use crate::fixtures::hardest_classic;
+    use crate::heuristics::{get_heuristic, HeuristicFunc};
+    use crate::progress::Silent;
+
+    #[test]
+    fn test_hda_star_optimal() {
+        let (start, goal) = hardest_classic();
+        let mut h = get_heuristic(HeuristicFunc::ManhattanDistance);
+        let limits = Limits::default();
+        let optimal =
+            a_star(start.clone(), &goal, h.as_mut(), &limits, &mut Silent)
+                .solution()
+                .unwrap();
+
+        for threads in [1, 2, 4] {
+            let found = hda_star(
+                start.clone(),
+                &goal,
+                h.as_mut(),
+                threads,
+                &limits,
+                &mut Silent,
+            )
+            .solution()
+            .unwrap();
+            assert_eq!(found.moves.len(), optimal.moves.len());
+            assert_eq!(found.end(), goal);
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use super::goal::Layout;
 use super::graph::LinearConflictGraph;
 use super::moves::Move;
 use super::puzzle::Puzzle;
@@ -13,7 +14,18 @@ pub enum HeuristicFunc {
 
 pub trait Heuristic {
     fn first_time(&mut self, p1: &Puzzle, p2: &Puzzle) -> u32;
-    fn difference(&mut self, p1: &Puzzle, p2: &Puzzle) -> u32;
+
+    // Value of `p1` given `h`, the value of the board it was one move
+    // before, without looking at the whole board when possible
+    //
+    fn update(&mut self, h: u32, p1: &Puzzle, p2: &Puzzle) -> u32 {
+        let _ = h;
+        self.first_time(p1, p2)
+    }
+
+    // A fresh copy for a search running on another thread
+    //
+    fn fork(&self) -> Box<dyn Heuristic + Send>;
 }
 
 struct Zero {}
@@ -29,8 +41,8 @@ impl Heuristic for Zero {
         Zero::zero(p1, p2)
     }
 
-    fn difference(&mut self, p1: &Puzzle, p2: &Puzzle) -> u32 {
-        0
+    fn fork(&self) -> Box<dyn Heuristic + Send> {
+        Box::new(Zero {})
     }
 }
 
@@ -39,27 +51,36 @@ struct HammingDistance {
 }
 
 impl HammingDistance {
-    fn hamming_distance(p: &Puzzle, _: &Puzzle) -> u32 {
+    fn misplaced(p: &Puzzle, layout: &Layout) -> u32 {
         let mut misplaced = 0;
         for i in 0..p.flat.len() {
             if p.flat[i] == 0 {
                 continue;
             }
-            if i != p.end[p.flat[i] as usize] {
+            if layout.cell(p.flat[i]).is_some_and(|goal| goal != i) {
                 misplaced += 1;
             }
         }
         misplaced
     }
 
+    fn hamming_distance(p: &Puzzle, _: &Puzzle) -> u32 {
+        p.end
+            .layouts()
+            .iter()
+            .map(|layout| HammingDistance::misplaced(p, layout))
+            .min()
+            .unwrap_or(0)
+    }
+
     fn last_tile_score(&mut self, p: &Puzzle) -> u32 {
         let last_blank = p.last_blank_index();
         let moved_tile = p.flat[last_blank];
 
         let actual = p.blank;
-        let goal = p.end[moved_tile as usize];
+        let goal = p.end.single().and_then(|l| l.cell(moved_tile));
 
-        if actual != goal {
+        if goal.is_some_and(|goal| actual != goal) {
             1
         } else {
             0
@@ -71,9 +92,9 @@ impl HammingDistance {
         let moved_tile = p.flat[last_blank];
 
         let actual = last_blank;
-        let goal = p.end[moved_tile as usize];
+        let goal = p.end.single().and_then(|l| l.cell(moved_tile));
 
-        if actual != goal {
+        if goal.is_some_and(|goal| actual != goal) {
             1
         } else {
             0
@@ -88,34 +109,46 @@ impl Heuristic for HammingDistance {
         self.misplaced
     }
 
-    fn difference(&mut self, p1: &Puzzle, p2: &Puzzle) -> u32 {
-        self.misplaced -= self.last_tile_score(p1);
-        self.misplaced += self.new_tile_score(p1);
+    fn update(&mut self, h: u32, p1: &Puzzle, p2: &Puzzle) -> u32 {
+        if p1.was == Move::Hold || p1.end.single().is_none() {
+            return self.first_time(p1, p2);
+        }
+        h + self.new_tile_score(p1) - self.last_tile_score(p1)
+    }
 
-        self.misplaced
+    fn fork(&self) -> Box<dyn Heuristic + Send> {
+        Box::new(HammingDistance { misplaced: 0 })
     }
 }
 
 struct ManhattanDistance {}
 
 impl ManhattanDistance {
+    fn layout_distance(p: &Puzzle, layout: &Layout) -> u32 {
+        p.flat
+            .iter()
+            .enumerate()
+            .map(|(i, &t)| layout.manhattan(t, i))
+            .sum()
+    }
+
     fn manhattan_distance(p: &Puzzle, _: &Puzzle) -> u32 {
-        let mut distance: u32 = 0;
-        for i in 0..p.flat.len() {
-            if p.flat[i] == 0 {
-                continue;
-            }
+        p.end
+            .layouts()
+            .iter()
+            .map(|layout| ManhattanDistance::layout_distance(p, layout))
+            .min()
+            .unwrap_or(0)
+    }
 
-            let j = p.end[p.flat[i] as usize];
-            let x1 = i % p.n;
-            let x2 = j % p.n;
-            let y1 = i / p.n;
-            let y2 = j / p.n;
-            let dx = x1 as i16 - x2 as i16;
-            let dy = y1 as i16 - y2 as i16;
-            distance += (dx.abs() + dy.abs()) as u32;
-        }
-        distance
+    // Only the tile that slid into the last blank has moved
+    //
+    fn manhattan_update(h: u32, p: &Puzzle, layout: &Layout) -> u32 {
+        let last_blank = p.last_blank_index();
+        let moved_tile = p.flat[last_blank];
+
+        h + layout.manhattan(moved_tile, last_blank)
+            - layout.manhattan(moved_tile, p.blank)
     }
 }
 
@@ -124,27 +157,42 @@ impl Heuristic for ManhattanDistance {
         ManhattanDistance::manhattan_distance(p1, p2)
     }
 
-    fn difference(&mut self, p1: &Puzzle, p2: &Puzzle) -> u32 {
-        0
+    // The lowest of several layouts can't be followed a move at a time
+    //
+    fn update(&mut self, h: u32, p1: &Puzzle, p2: &Puzzle) -> u32 {
+        match p1.end.single() {
+            Some(layout) => {
+                ManhattanDistance::manhattan_update(h, p1, layout)
+            }
+            None => self.first_time(p1, p2),
+        }
+    }
+
+    fn fork(&self) -> Box<dyn Heuristic + Send> {
+        Box::new(ManhattanDistance {})
     }
 }
 
 struct LinearConflicts {}
 
 impl LinearConflicts {
-    fn linear_col_conflicts(p: &Puzzle, col: usize) -> u32 {
+    fn linear_col_conflicts(
+        p: &Puzzle,
+        layout: &Layout,
+        col: usize,
+    ) -> u32 {
         let mut lc = 0;
         let mut lng = LinearConflictGraph::new();
         for row1 in 0..p.n {
             for row2 in (row1 + 1)..p.n {
-                let tile1 = Tile::new(p, col, row1);
-                let tile2 = Tile::new(p, col, row2);
+                let tile1 = Tile::new(p, layout, col, row1);
+                let tile2 = Tile::new(p, layout, col, row2);
                 if tile1.is_in_col_conflict_with(&tile2) {
                     lng.push_conflict(tile1.value, tile2.value);
                 }
             }
         }
-        while lng.is_conflicts() == true {
+        while lng.is_conflicts() {
             let tile = lng.most_conflicts();
             lng.remove_conflict_with(tile);
             lc += 1
@@ -152,19 +200,23 @@ impl LinearConflicts {
         lc
     }
 
-    fn linear_row_conflicts(p: &Puzzle, row: usize) -> u32 {
+    fn linear_row_conflicts(
+        p: &Puzzle,
+        layout: &Layout,
+        row: usize,
+    ) -> u32 {
         let mut lc = 0;
         let mut lng = LinearConflictGraph::new();
         for col1 in 0..p.n {
             for col2 in (col1 + 1)..p.n {
-                let tile1 = Tile::new(p, col1, row);
-                let tile2 = Tile::new(p, col2, row);
+                let tile1 = Tile::new(p, layout, col1, row);
+                let tile2 = Tile::new(p, layout, col2, row);
                 if tile1.is_in_row_conflict_with(&tile2) {
                     lng.push_conflict(tile1.value, tile2.value);
                 }
             }
         }
-        while lng.is_conflicts() == true {
+        while lng.is_conflicts() {
             let tile = lng.most_conflicts();
             lng.remove_conflict_with(tile);
             lc += 1
@@ -172,11 +224,11 @@ impl LinearConflicts {
         lc
     }
 
-    fn linear_conflicts_sum(p: &Puzzle) -> u32 {
+    fn linear_conflicts_sum(p: &Puzzle, layout: &Layout) -> u32 {
         (0..p.n)
             .map(|i| {
-                LinearConflicts::linear_row_conflicts(p, i)
-                    + LinearConflicts::linear_col_conflicts(p, i)
+                LinearConflicts::linear_row_conflicts(p, layout, i)
+                    + LinearConflicts::linear_col_conflicts(p, layout, i)
             })
             .sum()
     }
@@ -184,9 +236,56 @@ impl LinearConflicts {
     // [https://medium.com/swlh/looking-into-k-puzzle-heuristics-6189318eaca2]
     // [https://cse.sc.edu/~mgv/csce580sp15/gradPres/HanssonMayerYung1992.pdf]
     //
-    fn linear_conflicts(p1: &Puzzle, p2: &Puzzle) -> u32 {
-        ManhattanDistance::manhattan_distance(p1, p2)
-            + LinearConflicts::linear_conflicts_sum(p1) * 2
+    fn linear_conflicts(p1: &Puzzle, _: &Puzzle) -> u32 {
+        p1.end
+            .layouts()
+            .iter()
+            .map(|layout| {
+                ManhattanDistance::layout_distance(p1, layout)
+                    + LinearConflicts::linear_conflicts_sum(p1, layout) * 2
+            })
+            .min()
+            .unwrap_or(0)
+    }
+
+    // The moved tile changed row on a vertical move, or column on a
+    // horizontal one: only those two lines can have different conflicts,
+    // the order of the tiles in the other direction is the same
+    //
+    fn lines_conflicts(
+        p: &Puzzle,
+        layout: &Layout,
+        a: usize,
+        b: usize,
+    ) -> u32 {
+        match p.was {
+            Move::Up | Move::Down => {
+                LinearConflicts::linear_row_conflicts(p, layout, a / p.n)
+                    + LinearConflicts::linear_row_conflicts(
+                        p,
+                        layout,
+                        b / p.n,
+                    )
+            }
+            _ => {
+                LinearConflicts::linear_col_conflicts(p, layout, a % p.n)
+                    + LinearConflicts::linear_col_conflicts(
+                        p,
+                        layout,
+                        b % p.n,
+                    )
+            }
+        }
+    }
+
+    fn linear_update(h: u32, p: &Puzzle, layout: &Layout) -> u32 {
+        let mut before = p.clone();
+        p.was.apply(&mut before);
+        let (a, b) = (p.blank, p.last_blank_index());
+
+        ManhattanDistance::manhattan_update(h, p, layout)
+            + LinearConflicts::lines_conflicts(p, layout, a, b) * 2
+            - LinearConflicts::lines_conflicts(&before, layout, a, b) * 2
     }
 }
 
@@ -195,8 +294,17 @@ impl Heuristic for LinearConflicts {
         LinearConflicts::linear_conflicts(p1, p2)
     }
 
-    fn difference(&mut self, p1: &Puzzle, p2: &Puzzle) -> u32 {
-        0
+    fn update(&mut self, h: u32, p1: &Puzzle, p2: &Puzzle) -> u32 {
+        match p1.end.single() {
+            Some(layout) if p1.was != Move::Hold => {
+                LinearConflicts::linear_update(h, p1, layout)
+            }
+            _ => self.first_time(p1, p2),
+        }
+    }
+
+    fn fork(&self) -> Box<dyn Heuristic + Send> {
+        Box::new(LinearConflicts {})
     }
 }
 
@@ -214,6 +322,7 @@ pub fn get_heuristic(heuristic: HeuristicFunc) -> Box<dyn Heuristic> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::puzzle::PuzzleKind;
 
     #[test]
     fn test_classic_manhattan() {
@@ -365,9 +474,27 @@ mod tests {
         sum_3.set_goal(&goal_classic);
         sum_5.set_goal(&goal_classic);
 
-        assert_eq!(LinearConflicts::linear_conflicts_sum(&sum_1), 1);
-        assert_eq!(LinearConflicts::linear_conflicts_sum(&sum_3), 3);
-        assert_eq!(LinearConflicts::linear_conflicts_sum(&sum_5), 5);
+        assert_eq!(
+            LinearConflicts::linear_conflicts_sum(
+                &sum_1,
+                sum_1.end.single().unwrap()
+            ),
+            1
+        );
+        assert_eq!(
+            LinearConflicts::linear_conflicts_sum(
+                &sum_3,
+                sum_3.end.single().unwrap()
+            ),
+            3
+        );
+        assert_eq!(
+            LinearConflicts::linear_conflicts_sum(
+                &sum_5,
+                sum_5.end.single().unwrap()
+            ),
+            5
+        );
     }
 
     #[test]
@@ -394,6 +521,29 @@ mod tests {
         let mut h = get_heuristic(HeuristicFunc::HammingDistance);
 
         assert_eq!(h.first_time(&p1, &p2), 8);
-        assert_eq!(h.difference(&p2, &p2), 7);
+        assert_eq!(h.update(8, &p2, &p2), 7);
+    }
+
+    #[test]
+    fn test_update_matches_first_time() {
+        let goal = Puzzle::new(PuzzleKind::Snail, 4);
+        let mut p = goal.clone();
+        p.set_goal(&goal);
+        for heuristic in [
+            HeuristicFunc::HammingDistance,
+            HeuristicFunc::ManhattanDistance,
+            HeuristicFunc::LinearConflicts,
+        ] {
+            let mut h = get_heuristic(heuristic);
+            let mut value = h.first_time(&p, &goal);
+            for i in 0..500 {
+                let moves = Move::moves(&p);
+                let neighbor =
+                    p.neighbors().swap_remove(i * 7 % moves.len());
+                value = h.update(value, &neighbor, &goal);
+                p = neighbor;
+                assert_eq!(value, h.first_time(&p, &goal));
+            }
+        }
     }
 }
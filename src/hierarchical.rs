@@ -0,0 +1,150 @@
+use super::constructive::Board;
+use super::heuristics::{get_heuristic, Heuristic, HeuristicFunc};
+use super::ida_star::ida_star;
+use super::pattern::PatternDatabase;
+use super::progress::Progress;
+use super::puzzle::Puzzle;
+use super::solution::Solution;
+use super::solver::{Limits, Outcome, Solver, Stats};
+
+// Side of the square left for the optimal search, a 4x4 pattern database
+// takes a few seconds to build
+//
+pub const DEFAULT_INNER: usize = 4;
+
+// A group of the pattern database of a larger square takes hundreds of
+// megabytes to build
+//
+pub const MAX_INNER: usize = 4;
+
+// Peels the outer lines like the constructive solver until an `inner`
+// square is left, then solves that square optimally with IDA* and a
+// pattern database. Only the lines peeled are far from optimal, the
+// linear conflicts of the start board tell how far at most, see
+// lower_bound.
+//
+pub fn hierarchical(
+    start: Puzzle,
+    end: &Puzzle,
+    inner: usize,
+    limits: &Limits,
+    progress: &mut dyn Progress,
+) -> Outcome {
+    let mut board = Board::new(&start);
+    let rect = board.reduce(end, inner, limits);
+    let mut stats = Stats {
+        total_opened: board.opened,
+        max_states: board.flat.len(),
+    };
+    let rect = match rect {
+        Some(rect) => rect,
+        None if limits.exceeded(board.opened) => {
+            return Outcome::Interrupted(stats)
+        }
+        None => return Outcome::Unsolvable(stats),
+    };
+
+    let (sub_start, sub_goal) = board.sub_puzzle(end, &rect);
+    let mut pdb = PatternDatabase::build(&sub_goal);
+    let outcome =
+        ida_star(sub_start, &sub_goal, &mut pdb, None, limits, progress);
+    let found = outcome.stats();
+    stats.total_opened += found.total_opened;
+    stats.max_states = stats.max_states.max(found.max_states);
+    match outcome {
+        Outcome::Solved(solution) => {
+            board.moves.extend(solution.moves);
+            Outcome::Solved(Solution {
+                stats,
+                start,
+                moves: board.moves,
+            })
+        }
+        Outcome::Unsolvable(_) => Outcome::Unsolvable(stats),
+        Outcome::Interrupted(_) => Outcome::Interrupted(stats),
+    }
+}
+
+// Fewest moves any solution of the board needs: every heuristic is
+// admissible and linear conflicts dominates the other ones
+//
+pub fn lower_bound(start: &Puzzle, end: &Puzzle) -> u32 {
+    get_heuristic(HeuristicFunc::LinearConflicts).first_time(start, end)
+}
+
+pub struct Hierarchical {
+    pub inner: usize,
+    // lower bound of the board of the last solve
+    pub lower_bound: u32,
+}
+
+impl Solver for Hierarchical {
+    fn name(&self) -> &'static str {
+        "Hierarchical"
+    }
+
+    fn solve(
+        &mut self,
+        start: Puzzle,
+        end: &Puzzle,
+        _: &mut dyn Heuristic,
+        limits: &Limits,
+        progress: &mut dyn Progress,
+    ) -> Outcome {
+        self.lower_bound = lower_bound(&start, end);
+        hierarchical(start, end, self.inner, limits, progress)
+    }
+
+    fn details(&self) -> Vec<String> {
+        vec![format!("Lower bound: {}", self.lower_bound)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::a_star::a_star;
+    use crate::progress::Silent;
+    use crate::puzzle::{Difficulty, PuzzleKind};
+
+    #[test]
+    fn test_optimal_inside() {
+        let limits = Limits::default();
+        for kind in
+            [PuzzleKind::Classic, PuzzleKind::Snail, PuzzleKind::Reverse]
+        {
+            // nothing to peel, the whole board is solved optimally
+            let goal = Puzzle::new(kind, 3);
+            let mut start =
+                Puzzle::new_randomized(kind, Difficulty::Hard, 3);
+            start.set_goal(&goal);
+            let mut h = get_heuristic(HeuristicFunc::ManhattanDistance);
+            let optimal = a_star(
+                start.clone(),
+                &goal,
+                h.as_mut(),
+                &limits,
+                &mut Silent,
+            )
+            .solution()
+            .unwrap();
+            let found =
+                hierarchical(start, &goal, 3, &limits, &mut Silent)
+                    .solution()
+                    .unwrap();
+            assert_eq!(found.moves.len(), optimal.moves.len());
+
+            let goal = Puzzle::new(kind, 7);
+            let mut start =
+                Puzzle::new_randomized(kind, Difficulty::Hard, 7);
+            start.set_goal(&goal);
+            let bound = lower_bound(&start, &goal);
+            let found =
+                hierarchical(start, &goal, 3, &limits, &mut Silent)
+                    .solution()
+                    .unwrap();
+            assert_eq!(found.end(), goal);
+            assert!(found.moves.len() >= bound as usize);
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use super::heuristics::{get_heuristic, Heuristic, HeuristicFunc};
+use super::ida_star::{first_moves, ida_star};
+use super::moves::Move;
+use super::pattern::PatternDatabase;
+use super::progress::Silent;
+use super::puzzle::Puzzle;
+use super::solver::{Limits, Outcome, Stats};
+use super::validity;
+use super::zobrist::ZobristMap;
+use std::error::Error;
+use std::path::Path;
+
+// Above this side the pattern database takes minutes to build, the
+// linear conflicts are the strongest heuristic left
+//
+const MAX_PATTERN_SIDE: usize = 4;
+
+// Exact distances kept before the cache is emptied
+//
+const MAX_CACHED: usize = 1 << 20;
+
+// What to play next: how many moves the board is from the goal and every
+// move that keeps the path optimal, any of them is a best move
+//
+pub struct Hint {
+    pub distance: u32,
+    pub moves: Vec<Move>,
+    pub stats: Stats,
+}
+
+// Answers hints for one goal, again and again as a game goes on. The
+// heuristic is built once, and the exact distance of every board on an
+// optimal path found so far is kept: a hint on one of them only looks for
+// its optimal moves.
+//
+pub struct Hinter {
+    end: Puzzle,
+    h: Box<dyn Heuristic>,
+    distances: ZobristMap<u64, u32>,
+}
+
+#[allow(clippy::result_large_err)]
+impl Hinter {
+    pub fn new(end: &Puzzle) -> Hinter {
+        let mut end = end.clone();
+        end.set_goal(&end.clone());
+        let h: Box<dyn Heuristic> = if end.n <= MAX_PATTERN_SIDE {
+            Box::new(PatternDatabase::shared(&end))
+        } else {
+            get_heuristic(HeuristicFunc::LinearConflicts)
+        };
+        Hinter {
+            end,
+            h,
+            distances: ZobristMap::default(),
+        }
+    }
+
+    // Same as new, with the pattern database kept in a file so it is only
+    // built once
+    //
+    pub fn stored(
+        end: &Puzzle,
+        path: &Path,
+    ) -> Result<Hinter, Box<dyn Error>> {
+        if end.n <= MAX_PATTERN_SIDE {
+            PatternDatabase::stored(end, path)?;
+        }
+        Ok(Hinter::new(end))
+    }
+
+    pub fn hint(
+        &mut self,
+        board: &Puzzle,
+        limits: &Limits,
+    ) -> Result<Hint, Outcome> {
+        let mut board = board.clone();
+        board.end = self.end.end.clone();
+        board.was = Move::Hold;
+        if !validity::check_puzzle(&board, &self.end) {
+            return Err(Outcome::Unsolvable(Stats::default()));
+        }
+        if self.distances.len() > MAX_CACHED {
+            self.distances.clear();
+        }
+        let mut stats = Stats::default();
+        let distance = match self.distances.get(&board.key) {
+            Some(&distance) => distance,
+            None => self.distance(&board, limits, &mut stats)?,
+        };
+
+        let (moves, found) = first_moves(
+            &board,
+            &self.end,
+            self.h.as_mut(),
+            distance,
+            limits,
+        )?;
+        stats.total_opened += found.total_opened;
+        for m in &moves {
+            let mut next = board.clone();
+            m.apply(&mut next);
+            self.distances.insert(next.key, distance - 1);
+        }
+        Ok(Hint {
+            distance,
+            moves,
+            stats,
+        })
+    }
+
+    fn distance(
+        &mut self,
+        board: &Puzzle,
+        limits: &Limits,
+        stats: &mut Stats,
+    ) -> Result<u32, Outcome> {
+        let end = &self.end;
+        match ida_star(
+            board.clone(),
+            end,
+            self.h.as_mut(),
+            None,
+            limits,
+            &mut Silent,
+        ) {
+            Outcome::Solved(solution) => {
+                stats.total_opened += solution.stats.total_opened;
+                let boards = solution.boards().collect::<Vec<_>>();
+                self.remember(&boards);
+                Ok(solution.moves.len() as u32)
+            }
+            outcome => Err(outcome),
+        }
+    }
+
+    // The boards of an optimal path, each one a move further from the
+    // goal than the next
+    //
+    fn remember(&mut self, path: &[Puzzle]) {
+        let last = path.len() as u32 - 1;
+        for (i, board) in path.iter().enumerate() {
+            self.distances.insert(board.key, last - i as u32);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enumerate::optimal_solutions;
+    use crate::fixtures::subject_1;
+
+    #[test]
+    fn test_hints_follow_optimal_solutions() {
+        let (start, goal) = subject_1();
+        let limits = Limits::default();
+        let mut hinter = Hinter::new(&goal);
+        let mut h = get_heuristic(HeuristicFunc::LinearConflicts);
+        let optimal = optimal_solutions(
+            start.clone(),
+            &goal,
+            h.as_mut(),
+            false,
+            &limits,
+            &mut Silent,
+        )
+        .ok()
+        .unwrap();
+
+        let hint = hinter.hint(&start, &limits).ok().unwrap();
+        assert_eq!(hint.distance as usize, optimal.length);
+        let mut first = optimal
+            .solutions
+            .iter()
+            .map(|moves| moves[0])
+            .collect::<Vec<_>>();
+        let mut moves = hint.moves.clone();
+        first.sort_by_key(|m| format!("{:?}", m));
+        first.dedup();
+        moves.sort_by_key(|m| format!("{:?}", m));
+        assert_eq!(moves, first);
+
+        // every hint is one move closer, down to the goal
+        let mut board = start.clone();
+        for left in (0..=hint.distance).rev() {
+            let hint = hinter.hint(&board, &limits).ok().unwrap();
+            assert_eq!(hint.distance, left);
+            assert_eq!(hint.moves.is_empty(), left == 0);
+            if let Some(m) = hint.moves.first() {
+                m.apply(&mut board);
+            }
+        }
+        assert_eq!(board, goal);
+    }
+}
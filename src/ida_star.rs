@@ -1,84 +1,374 @@
 use super::heuristics::Heuristic;
+use super::moves::Move;
+use super::progress::{Progress, Status};
+use super::pruning::MovePruning;
 use super::puzzle::Puzzle;
 use super::solution::Solution;
+use super::solver::{Limits, Outcome, Solver, Stats};
+use super::transposition::{Entry, TranspositionTable};
+use super::zobrist::ZobristSet;
 
 pub enum SearchResult {
     Found,
     NotFound,
     Minimum(u32),
+    Interrupted,
 }
 
 // [https://en.wikipedia.org/wiki/Iterative_deepening_A*]
 // [https://github.com/samueltardieu/pathfinding/blob/main/src/directed/idastar.rs]
 //
+//
+// With a transposition table, a board reached again with a higher g, or
+// with the same g and machine state in the same iteration, is not searched
+// twice. The table also keeps the best lower bound found for each board,
+// the lowest of its neighbors plus one, better than the heuristic as the
+// iterations go on.
+//
 pub fn ida_star(
     start: Puzzle,
-    end: Puzzle,
+    end: &Puzzle,
     h: &mut dyn Heuristic,
-) -> Option<Solution> {
-    let mut bound = h.first_time(&start, &end);
-    let mut path = vec![start.clone()];
+    table: Option<&mut TranspositionTable>,
+    limits: &Limits,
+    progress: &mut dyn Progress,
+) -> Outcome {
+    let start_h = h.first_time(&start, end);
+    let mut bound = start_h;
+    let mut search = Search {
+        end,
+        h,
+        limits,
+        pruning: MovePruning::shared(),
+        on_path: ZobristSet::default(),
+        table,
+        iteration: 0,
+        nodes: 0,
+        total_opened: 0,
+    };
+    search.on_path.insert(start.key);
+    let mut path = vec![start];
 
     loop {
-        match search(&mut path, 0, bound, &end, h) {
+        search.nodes = 0;
+        let state = search.pruning.start();
+        let result = search.search(&mut path, 0, start_h, bound, state);
+        search.total_opened += search.nodes;
+        progress.report(&Status::Iteration {
+            bound,
+            nodes: search.nodes,
+        });
+        let stats = Stats {
+            total_opened: search.total_opened,
+            max_states: path.len(),
+        };
+        match result {
             SearchResult::Found => {
-                return Some(Solution {
-                    total_opened: 0,
-                    max_states: 0,
-                    path,
-                })
+                return Outcome::Solved(Solution::from_path(stats, path))
+            }
+            SearchResult::Minimum(m) => {
+                bound = m;
+                search.iteration += 1;
+            }
+            SearchResult::NotFound => return Outcome::Unsolvable(stats),
+            SearchResult::Interrupted => {
+                return Outcome::Interrupted(stats)
             }
-            SearchResult::Minimum(m) => bound = m,
-            SearchResult::NotFound => return None,
         }
     }
 }
 
-fn search(
-    path: &mut Vec<Puzzle>,
-    g: u32,
-    bound: u32,
+// The first move of every path from `start` to the goal in at most
+// `bound` moves: all the optimal moves when `bound` is the distance of the
+// board. The last iteration of IDA* goes on under each first move the
+// pruning keeps, and stops there at the first path found.
+//
+#[allow(clippy::result_large_err)]
+pub fn first_moves(
+    start: &Puzzle,
     end: &Puzzle,
     h: &mut dyn Heuristic,
-) -> SearchResult {
-    let start = path.last().unwrap();
-    let f = g + h.first_time(&start, &end);
-    if f > bound {
-        return SearchResult::Minimum(f);
+    bound: u32,
+    limits: &Limits,
+) -> Result<(Vec<Move>, Stats), Outcome> {
+    let start_h = h.first_time(start, end);
+    let mut search = Search {
+        end,
+        h,
+        limits,
+        pruning: MovePruning::shared(),
+        on_path: ZobristSet::default(),
+        table: None,
+        iteration: 0,
+        nodes: 0,
+        total_opened: 0,
+    };
+    let root = search.pruning.start();
+    let mut moves = vec![];
+    for m in Move::moves(start) {
+        let state = match search.pruning.next(root, m) {
+            Some(state) => state,
+            None => continue,
+        };
+        let mut p = start.clone();
+        m.apply(&mut p);
+        let h = search.h.update(start_h, &p, end);
+        search.on_path.clear();
+        search.on_path.insert(start.key);
+        search.on_path.insert(p.key);
+        let mut path = vec![start.clone(), p];
+        match search.search(&mut path, 1, h, bound, state) {
+            SearchResult::Found => moves.push(m),
+            SearchResult::Interrupted => {
+                return Err(Outcome::Interrupted(Stats {
+                    total_opened: search.nodes,
+                    ..Stats::default()
+                }))
+            }
+            SearchResult::Minimum(_) | SearchResult::NotFound => (),
+        }
     }
-    if start == end {
-        return SearchResult::Found;
+    let stats = Stats {
+        total_opened: search.nodes,
+        max_states: bound as usize + 1,
+    };
+    Ok((moves, stats))
+}
+
+struct Search<'a> {
+    end: &'a Puzzle,
+    h: &'a mut dyn Heuristic,
+    limits: &'a Limits,
+    pruning: &'a MovePruning,
+    // zobrist keys of the boards on the path, a collision of 64 bits keys
+    // between two boards of the same path is not worth a full comparison
+    on_path: ZobristSet<u64>,
+    table: Option<&'a mut TranspositionTable>,
+    iteration: u32,
+    nodes: usize,
+    total_opened: usize,
+}
+
+impl Search<'_> {
+    fn search(
+        &mut self,
+        path: &mut Vec<Puzzle>,
+        g: u32,
+        h: u32,
+        bound: u32,
+        state: usize,
+    ) -> SearchResult {
+        self.nodes += 1;
+        if self.limits.exceeded(self.total_opened + self.nodes) {
+            return SearchResult::Interrupted;
+        }
+        let start = path.last().unwrap();
+        let learned = match self.lookup(start.key, g, state) {
+            Some(learned) => learned,
+            None => return SearchResult::NotFound,
+        };
+        let f = g + h.max(learned);
+        if f > bound {
+            return SearchResult::Minimum(f);
+        }
+        if start.is_goal(self.end) {
+            return SearchResult::Found;
+        }
+        let mut min = None;
+        let mut neighbors = Move::moves(start)
+            .into_iter()
+            .filter_map(|m| {
+                let state = self.pruning.next(state, m)?;
+                let mut p = start.clone();
+                m.apply(&mut p);
+                if self.on_path.contains(&p.key) {
+                    None
+                } else {
+                    let h = self.h.update(h, &p, self.end);
+                    Some((p, h, state))
+                }
+            })
+            .collect::<Vec<_>>();
+        neighbors.sort_by_key(|&(_, h, _)| h);
+        for (node, h, state) in neighbors {
+            self.on_path.insert(node.key);
+            path.push(node);
+            let t = self.search(path, g + 1, h, bound, state);
+            match t {
+                SearchResult::Minimum(m) => match min {
+                    None => min = Some(m),
+                    Some(n) if m < n => min = Some(m),
+                    Some(_) => (),
+                },
+                SearchResult::Found => return SearchResult::Found,
+                SearchResult::Interrupted => {
+                    return SearchResult::Interrupted
+                }
+                SearchResult::NotFound => (),
+            };
+            if let Some(node) = path.pop() {
+                self.on_path.remove(&node.key);
+            }
+        }
+        self.learn(path.last().unwrap(), h);
+
+        match min {
+            Some(m) => SearchResult::Minimum(m),
+            None => SearchResult::NotFound,
+        }
     }
-    let mut min = None;
-    let mut neighbors = start
-        .neighbors()
-        .into_iter()
-        .filter_map(|p| {
-            if path.contains(&p) {
-                None
-            } else {
-                Some((p.clone(), g + h.first_time(&p, end) + 1))
+
+    // The bound learned for the board so far, none when it must not be
+    // searched again. The board is recorded as explored at this g.
+    //
+    fn lookup(&mut self, key: u64, g: u32, state: usize) -> Option<u32> {
+        let table = match &mut self.table {
+            Some(table) => table,
+            None => return Some(0),
+        };
+        let state = state as u32;
+        let mut learned = 0;
+        if let Some(entry) = table.get(key) {
+            let same_subtree = entry.g == g
+                && entry.state == state
+                && entry.iteration == self.iteration;
+            if entry.g < g || same_subtree {
+                table.hits += 1;
+                return None;
             }
-        })
-        .collect::<Vec<_>>();
-    neighbors.sort_by_key(|&(_, c)| c);
-    for (node, _) in neighbors {
-        path.push(node);
-        let t = search(path, g + 1, bound, &end, h);
-        match t {
-            SearchResult::Minimum(m) => match min {
-                None => min = Some(m),
-                Some(n) if m < n => min = Some(m),
-                Some(_) => (),
-            },
-            SearchResult::Found => return SearchResult::Found,
-            SearchResult::NotFound => (),
+            learned = entry.h;
+        }
+        table.insert(Entry {
+            key,
+            g,
+            h: learned,
+            state,
+            iteration: self.iteration,
+        });
+        Some(learned)
+    }
+
+    // Every neighbor, the one the board came from included, is at least
+    // its own bound away from the goal
+    //
+    fn learn(&mut self, puzzle: &Puzzle, h: u32) {
+        let table = match &self.table {
+            Some(table) => table,
+            None => return,
         };
-        path.pop();
+        let mut from = puzzle.clone();
+        from.was = Move::Hold;
+        let mut best = None;
+        for m in Move::moves(&from) {
+            let mut p = from.clone();
+            m.apply(&mut p);
+            let learned = table.get(p.key).map_or(0, |e| e.h);
+            let bound = 1 + self.h.update(h, &p, self.end).max(learned);
+            best = Some(best.map_or(bound, |b: u32| b.min(bound)));
+        }
+        if let (Some(best), Some(table)) = (best, &mut self.table) {
+            if let Some(entry) = table.get(puzzle.key) {
+                let mut entry = *entry;
+                entry.h = entry.h.max(best);
+                table.insert(entry);
+            }
+        }
     }
+}
+
+pub struct IDAStar {
+    // entries of the transposition table, none without one
+    pub table_size: usize,
+    // subtrees the table let the last solve skip
+    pub hits: usize,
+}
 
-    match min {
-        Some(m) => SearchResult::Minimum(m),
-        None => SearchResult::NotFound,
+impl Solver for IDAStar {
+    fn name(&self) -> &'static str {
+        "IDA*"
+    }
+
+    fn solve(
+        &mut self,
+        start: Puzzle,
+        end: &Puzzle,
+        h: &mut dyn Heuristic,
+        limits: &Limits,
+        progress: &mut dyn Progress,
+    ) -> Outcome {
+        if self.table_size == 0 {
+            return ida_star(start, end, h, None, limits, progress);
+        }
+        let mut table = TranspositionTable::new(self.table_size);
+        let outcome =
+            ida_star(start, end, h, Some(&mut table), limits, progress);
+        self.hits = table.hits;
+        outcome
+    }
+
+    fn details(&self) -> Vec<String> {
+        match self.table_size {
+            0 => vec![],
+            _ => vec![format!("Transposition hits: {}", self.hits)],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::hardest_classic;
+    use crate::heuristics::{get_heuristic, HeuristicFunc};
+    use crate::progress::Silent;
+
+    #[test]
+    fn test_transposition_table() {
+        let (start, goal) = hardest_classic();
+        let mut h = get_heuristic(HeuristicFunc::ManhattanDistance);
+        let mut search = |table| {
+            ida_star(
+                start.clone(),
+                &goal,
+                h.as_mut(),
+                table,
+                &Limits::default(),
+                &mut Silent,
+            )
+            .solution()
+            .unwrap()
+        };
+
+        let plain = search(None);
+        let mut table = TranspositionTable::new(1 << 16);
+        let with_table = search(Some(&mut table));
+        let mut tiny = TranspositionTable::new(7);
+        let tiny_table = search(Some(&mut tiny));
+
+        assert_eq!(plain.moves.len(), 31);
+        assert_eq!(with_table.moves.len(), 31);
+        assert_eq!(tiny_table.moves.len(), 31);
+        assert_eq!(with_table.end(), goal);
+        assert!(table.hits > 0);
+        assert!(with_table.stats.total_opened < plain.stats.total_opened);
+
+        let mut solver = IDAStar {
+            table_size: 1 << 16,
+            hits: 0,
+        };
+        let solved = solver
+            .solve(
+                start.clone(),
+                &goal,
+                h.as_mut(),
+                &Limits::default(),
+                &mut Silent,
+            )
+            .solution()
+            .unwrap();
+        assert_eq!(
+            solved.stats.total_opened,
+            with_table.stats.total_opened
+        );
+        assert_eq!(solver.hits, table.hits);
     }
 }
@@ -0,0 +1,237 @@
+use super::heuristics::{get_heuristic, HeuristicFunc};
+use super::ida_star::ida_star;
+use super::moves::Move;
+use super::progress::Silent;
+use super::puzzle::Puzzle;
+use super::solver::{Limits, Outcome};
+use super::zobrist::ZobristMap;
+
+// Moves of a segment handed to IDA* at once
+//
+pub const DEFAULT_WINDOW: usize = 16;
+
+// IDA* gives up on a segment after opening this many states
+//
+const SEGMENT_NODES: usize = 200_000;
+
+// Cuts every loop of the path: when a board comes back, the moves made
+// since its first visit are dropped. Boards are recognised by their
+// zobrist key only, `improve` checks the result is still legal.
+//
+pub fn remove_loops(start: &Puzzle, moves: &[Move]) -> Vec<Move> {
+    let mut board = start.clone();
+    let mut keys = vec![board.key];
+    let mut seen: ZobristMap<u64, usize> = ZobristMap::default();
+    seen.insert(board.key, 0);
+    let mut out = vec![];
+    for m in moves {
+        m.apply(&mut board);
+        match seen.get(&board.key) {
+            Some(&i) => {
+                for key in keys.drain(i + 1..) {
+                    seen.remove(&key);
+                }
+                out.truncate(i);
+            }
+            None => {
+                out.push(*m);
+                keys.push(board.key);
+                seen.insert(board.key, out.len());
+            }
+        }
+    }
+    out
+}
+
+// The smallest square around every cell the blank goes through, as its
+// top left cell and side
+//
+fn square_around(
+    board: &Puzzle,
+    segment: &[Move],
+) -> (usize, usize, usize) {
+    let n = board.n;
+    let (mut row, mut column) = (board.blank / n, board.blank % n);
+    let (mut top, mut bottom, mut left, mut right) =
+        (row, row, column, column);
+    for m in segment {
+        match m {
+            Move::Up => row -= 1,
+            Move::Down => row += 1,
+            Move::Left => column -= 1,
+            Move::Right => column += 1,
+            Move::Hold => (),
+        }
+        top = top.min(row);
+        bottom = bottom.max(row);
+        left = left.min(column);
+        right = right.max(column);
+    }
+    let side = (bottom - top + 1).max(right - left + 1).max(2);
+    (top.min(n - side), left.min(n - side), side)
+}
+
+// Optimal moves between the boards at both ends of a segment, only when
+// they are fewer. The tiles outside the square the blank goes through
+// don't move so the search runs on that square alone.
+//
+fn shorter_segment(
+    board: &Puzzle,
+    segment: &[Move],
+    limits: &Limits,
+) -> Option<Vec<Move>> {
+    let n = board.n;
+    let (top, left, side) = square_around(board, segment);
+    let flat = (0..side * side)
+        .map(|i| board.flat[(top + i / side) * n + left + i % side])
+        .collect::<Vec<_>>();
+    let mut labels = flat.clone();
+    labels.sort_unstable();
+    let relabeled = flat
+        .iter()
+        .map(|t| labels.binary_search(t).unwrap() as u16)
+        .collect::<Vec<_>>();
+
+    let mut from = Puzzle::from_flat(side, relabeled);
+    let mut to = from.clone();
+    for m in segment {
+        m.apply(&mut to);
+    }
+    to.was = Move::Hold;
+    from.set_goal(&to);
+
+    let mut h = get_heuristic(HeuristicFunc::LinearConflicts);
+    if h.first_time(&from, &to) as usize >= segment.len() {
+        return None;
+    }
+    let limits = Limits {
+        max_nodes: Some(SEGMENT_NODES),
+        ..limits.clone()
+    };
+    match ida_star(from, &to, h.as_mut(), None, &limits, &mut Silent) {
+        Outcome::Solved(s) if s.moves.len() < segment.len() => {
+            Some(s.moves)
+        }
+        _ => None,
+    }
+}
+
+// Slides a window along the path and swaps in the optimal connection
+// wherever it is shorter than the moves of the window
+//
+pub fn shorten(
+    start: &Puzzle,
+    moves: &[Move],
+    window: usize,
+    limits: &Limits,
+) -> Vec<Move> {
+    let window = window.max(2);
+    let mut board = start.clone();
+    let mut out = Vec::with_capacity(moves.len());
+    let mut i = 0;
+    while i < moves.len() {
+        let j = (i + window).min(moves.len());
+        let segment = match shorter_segment(&board, &moves[i..j], limits) {
+            Some(shorter) => {
+                i = j;
+                shorter
+            }
+            None => {
+                let step = (window / 2).min(j - i);
+                i += step;
+                moves[i - step..i].to_vec()
+            }
+        };
+        for m in &segment {
+            m.apply(&mut board);
+        }
+        out.extend(segment);
+    }
+    out
+}
+
+// Applies the moves one after the other, None as soon as one of them
+// would take the blank off the board
+//
+fn replay(start: &Puzzle, moves: &[Move]) -> Option<Puzzle> {
+    let mut board = start.clone();
+    let n = board.n;
+    for m in moves {
+        let (row, column) = (board.blank / n, board.blank % n);
+        let legal = match m {
+            Move::Up => row > 0,
+            Move::Down => row + 1 < n,
+            Move::Left => column > 0,
+            Move::Right => column + 1 < n,
+            Move::Hold => true,
+        };
+        if !legal {
+            return None;
+        }
+        m.apply(&mut board);
+    }
+    Some(board)
+}
+
+// Removes the loops then shortens the windows, keeping the original path
+// if the result does not lead to the same board
+//
+pub fn improve(
+    start: &Puzzle,
+    moves: &[Move],
+    window: usize,
+    limits: &Limits,
+) -> Vec<Move> {
+    let improved = remove_loops(start, moves);
+    let improved = shorten(start, &improved, window, limits);
+    let improved = remove_loops(start, &improved);
+    match (replay(start, &improved), replay(start, moves)) {
+        (Some(a), Some(b)) if a == b => improved,
+        _ => moves.to_vec(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constructive::constructive;
+    use crate::puzzle::{Difficulty, PuzzleKind};
+
+    #[test]
+    fn test_remove_loops() {
+        let start = Puzzle::new(PuzzleKind::Snail, 3);
+        let moves = [
+            Move::Up,
+            Move::Left,
+            Move::Right,
+            Move::Down,
+            Move::Right,
+            Move::Left,
+            Move::Up,
+        ];
+        assert_eq!(remove_loops(&start, &moves), vec![Move::Up]);
+    }
+
+    #[test]
+    fn test_improve_keeps_ends() {
+        let limits = Limits::default();
+        for n in [4, 6, 9] {
+            let goal = Puzzle::new(PuzzleKind::Classic, n);
+            let mut start = Puzzle::new_randomized(
+                PuzzleKind::Classic,
+                Difficulty::Hard,
+                n,
+            );
+            start.set_goal(&goal);
+            let moves = constructive(start.clone(), &goal, &limits)
+                .solution()
+                .unwrap()
+                .moves;
+            let improved =
+                improve(&start, &moves, DEFAULT_WINDOW, &limits);
+
+            assert!(improved.len() < moves.len());
+            assert_eq!(replay(&start, &improved), Some(goal));
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use super::bucket::{BucketQueue, TieBreak};
+use super::heuristics::Heuristic;
+use super::ida_star::ida_star;
+use super::moves::Move;
+use super::progress::{Progress, Status};
+use super::puzzle::Puzzle;
+use super::solution::Solution;
+use super::solver::{Limits, Outcome, Stats};
+use super::zobrist::{ZobristMap, ZobristSet};
+
+// [https://en.wikipedia.org/wiki/Yen%27s_algorithm]
+//
+// The shortest solution comes from IDA*. Each next one branches off one
+// found before: it follows the same moves up to some board, the spur,
+// then takes the shortest way to the goal that avoids the boards already
+// behind and the moves the solutions found so far made from there. The
+// shortest of all those candidates is the next solution. Solutions never
+// go through the same board twice, no two of them are the same moves.
+//
+// Every solution found, shortest first, with the boards opened so far.
+// Fewer than `k` when there are no more or the last outcome is the reason
+// the search stopped.
+//
+pub fn k_shortest(
+    start: Puzzle,
+    end: &Puzzle,
+    h: &mut dyn Heuristic,
+    k: usize,
+    limits: &Limits,
+    progress: &mut dyn Progress,
+) -> Vec<Outcome> {
+    let first =
+        match ida_star(start.clone(), end, h, None, limits, progress) {
+            Outcome::Solved(first) => first,
+            outcome => return vec![outcome],
+        };
+    let mut yen = Yen {
+        end,
+        h,
+        limits,
+        opened: first.stats.total_opened,
+    };
+    let mut found = vec![first.moves.clone()];
+    let mut candidates: Vec<Vec<Move>> = vec![];
+    let mut outcomes = vec![Outcome::Solved(first)];
+
+    while found.len() < k {
+        let last = found.last().unwrap();
+        let mut board = start.clone();
+        let mut behind = ZobristSet::default();
+        for (i, m) in last.iter().enumerate() {
+            let root = &last[..i];
+            let taken = found
+                .iter()
+                .filter(|moves| moves.len() > i && moves[..i] == *root)
+                .map(|moves| moves[i])
+                .collect::<Vec<_>>();
+            match yen.shortest(&board, &behind, &taken) {
+                Some(Some(spur)) => {
+                    let mut moves = root.to_vec();
+                    moves.extend(spur);
+                    if !candidates.contains(&moves)
+                        && !found.contains(&moves)
+                    {
+                        candidates.push(moves);
+                    }
+                }
+                Some(None) => (),
+                None => {
+                    outcomes.push(Outcome::Interrupted(yen.stats()));
+                    return outcomes;
+                }
+            }
+            behind.insert(board.key);
+            m.apply(&mut board);
+        }
+
+        // the shortest candidate, the first found among equals
+        let next = candidates
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, moves)| moves.len())
+            .map(|(i, _)| i);
+        let moves = match next {
+            Some(i) => candidates.remove(i),
+            None => break,
+        };
+        progress.report(&Status::Iteration {
+            bound: moves.len() as u32,
+            nodes: yen.opened,
+        });
+        outcomes.push(Outcome::Solved(Solution {
+            stats: yen.stats(),
+            start: start.clone(),
+            moves: moves.clone(),
+        }));
+        found.push(moves);
+    }
+    outcomes
+}
+
+struct Yen<'a> {
+    end: &'a Puzzle,
+    h: &'a mut dyn Heuristic,
+    limits: &'a Limits,
+    opened: usize,
+}
+
+impl Yen<'_> {
+    fn stats(&self) -> Stats {
+        Stats {
+            total_opened: self.opened,
+            ..Stats::default()
+        }
+    }
+
+    // A* from `from` that never goes through the boards `behind` nor
+    // starts with one of the moves `taken`. None when the limits were hit,
+    // Some(None) when there is no such way to the goal.
+    //
+    fn shortest(
+        &mut self,
+        from: &Puzzle,
+        behind: &ZobristSet<u64>,
+        taken: &[Move],
+    ) -> Option<Option<Vec<Move>>> {
+        let mut open = BucketQueue::new(TieBreak::HighG);
+        // best g and the board and move it was reached from, by key
+        let mut parents: ZobristMap<u64, (u32, u64, Move)> =
+            ZobristMap::default();
+        let mut closed = ZobristSet::default();
+        let mut root = from.clone();
+        root.was = Move::Hold;
+        let h = self.h.first_time(&root, self.end);
+        open.push(h, 0, (root, 0, h));
+
+        while let Some((_, (puzzle, g, h))) = open.pop() {
+            if closed.contains(&puzzle.key)
+                || parents.get(&puzzle.key).is_some_and(|p| p.0 < g)
+            {
+                continue;
+            }
+            if puzzle.is_goal(self.end) {
+                let mut moves = vec![];
+                let mut key = puzzle.key;
+                while key != from.key {
+                    let (_, parent, m) = parents[&key];
+                    moves.push(m);
+                    key = parent;
+                }
+                moves.reverse();
+                return Some(Some(moves));
+            }
+            self.opened += 1;
+            if self.limits.exceeded(self.opened) {
+                return None;
+            }
+            closed.insert(puzzle.key);
+            for m in Move::moves(&puzzle) {
+                if g == 0 && taken.contains(&m) {
+                    continue;
+                }
+                let mut p = puzzle.clone();
+                m.apply(&mut p);
+                let g = g + 1;
+                if behind.contains(&p.key)
+                    || closed.contains(&p.key)
+                    || parents.get(&p.key).is_some_and(|p| p.0 <= g)
+                {
+                    continue;
+                }
+                parents.insert(p.key, (g, puzzle.key, m));
+                let h = self.h.update(h, &p, self.end);
+                open.push(g + h, g, (p, g, h));
+            }
+        }
+        Some(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enumerate::optimal_solutions;
+    use crate::fixtures::subject_1;
+    use crate::heuristics::{get_heuristic, HeuristicFunc};
+    use crate::progress::Silent;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_k_shortest() {
+        let (start, goal) = subject_1();
+        let mut h = get_heuristic(HeuristicFunc::LinearConflicts);
+        let limits = Limits::default();
+        let solutions = k_shortest(
+            start.clone(),
+            &goal,
+            h.as_mut(),
+            10,
+            &limits,
+            &mut Silent,
+        )
+        .into_iter()
+        .map(|outcome| outcome.solution().unwrap().moves)
+        .collect::<Vec<_>>();
+        let optimal = optimal_solutions(
+            start.clone(),
+            &goal,
+            h.as_mut(),
+            true,
+            &limits,
+            &mut Silent,
+        )
+        .ok()
+        .unwrap();
+
+        assert_eq!(solutions.len(), 10);
+        let lengths = solutions.iter().map(Vec::len).collect::<Vec<_>>();
+        assert!(lengths.windows(2).all(|w| w[0] <= w[1]));
+        let shortest = lengths.iter().filter(|&&l| l == optimal.length);
+        assert_eq!(shortest.count() as u64, optimal.count);
+        for (i, moves) in solutions.iter().enumerate() {
+            assert!(!solutions[..i].contains(moves));
+            let mut board = start.clone();
+            let mut seen = HashSet::new();
+            seen.insert(board.key);
+            for m in moves {
+                m.apply(&mut board);
+                assert!(seen.insert(board.key));
+            }
+            assert_eq!(board, goal);
+        }
+    }
+}
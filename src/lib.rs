@@ -0,0 +1,52 @@
+// The original code predates these lints, it is kept as it was
+//
+#![allow(
+    clippy::bool_assert_comparison,
+    clippy::len_zero,
+    clippy::manual_is_multiple_of,
+    clippy::needless_range_loop,
+    clippy::nonminimal_bool,
+    clippy::ptr_arg,
+    clippy::write_with_newline,
+    clippy::writeln_empty_string
+)]
+
+pub mod a_star;
+pub mod arena;
+pub mod beam;
+pub mod bfs;
+pub mod bucket;
+pub mod constructive;
+pub mod enumerate;
+#[cfg(test)]
+mod fixtures;
+pub mod generate;
+pub mod goal;
+pub mod graph;
+pub mod hda_star;
+pub mod heuristics;
+pub mod hierarchical;
+pub mod hint;
+pub mod ida_star;
+pub mod improve;
+pub mod inversions;
+pub mod k_shortest;
+pub mod moves;
+pub mod parsing;
+pub mod pattern;
+pub mod portfolio;
+pub mod progress;
+pub mod pruning;
+pub mod puzzle;
+pub mod rank;
+pub mod rbfs;
+pub mod real_time;
+pub mod sma_star;
+pub mod solution;
+pub mod solver;
+pub mod state;
+pub mod table;
+pub mod tile;
+pub mod transposition;
+pub mod validity;
+pub mod zobrist;
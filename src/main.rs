@@ -1,23 +1,29 @@
-mod a_star;
+// The original code predates these lints, it is kept as it was
+//
+#![allow(clippy::println_empty_string, clippy::ptr_arg)]
+
 mod args;
-mod generate;
-mod graph;
-mod heuristics;
-mod ida_star;
-mod inversions;
-mod moves;
-mod parsing;
-mod puzzle;
-mod solution;
-mod tile;
-mod validity;
 
 use std::error::Error;
 use std::path::Path;
 
 use args::{parse_args, Sia};
-use puzzle::Puzzle;
-use solution::solve;
+use n_puzzle::enumerate;
+use n_puzzle::goal::GoalSpec;
+use n_puzzle::heuristics::get_heuristic;
+use n_puzzle::hint::Hinter;
+use n_puzzle::improve::{improve, DEFAULT_WINDOW};
+use n_puzzle::k_shortest;
+use n_puzzle::moves::Move;
+use n_puzzle::parsing;
+use n_puzzle::portfolio;
+use n_puzzle::progress::{Progress, Silent, StatusLine};
+use n_puzzle::puzzle::Puzzle;
+use n_puzzle::real_time;
+use n_puzzle::solution::{solve, Algorithm};
+use n_puzzle::solver::{Outcome, Solver};
+use n_puzzle::table::{DistanceTable, TableSolver};
+use n_puzzle::validity;
 
 fn get_puzzle_from_file(f: &String) -> Result<Puzzle, Box<dyn Error>> {
 	let puzzle_path = Path::new(f);
@@ -31,6 +37,15 @@ fn get_puzzle_from_file(f: &String) -> Result<Puzzle, Box<dyn Error>> {
 	Ok(custom_puzzle)
 }
 
+fn get_goal_spec_from_file(f: &str) -> Result<GoalSpec, Box<dyn Error>> {
+	let goal_path = Path::new(f);
+	if !goal_path.is_file() {
+		return Err("Invalid goal file, the path is wrong !".into());
+	}
+
+	parsing::parse_goal(goal_path)
+}
+
 fn get_random_puzzle(options: &Sia) -> Puzzle {
 	Puzzle::new_randomized(options.kind, options.difficulty, options.size)
 }
@@ -43,20 +58,268 @@ fn get_puzzle(options: &Sia) -> Result<Puzzle, Box<dyn Error>> {
 	Ok(new_puzzle)
 }
 
-fn get_puzzle_goal(options: &Sia) -> Puzzle {
-	Puzzle::new(options.kind, options.size)
+// The goal is the size of the puzzle, -s only sizes generated ones
+//
+fn get_puzzle_goal(options: &Sia, size: usize) -> Puzzle {
+	Puzzle::new(options.kind, size)
+}
+
+fn get_solver(
+	options: &Sia,
+	goal: &Puzzle,
+) -> Result<Box<dyn Solver>, Box<dyn Error>> {
+	let table = match &options.table {
+		Some(t) => DistanceTable::load(Path::new(t))?,
+		None => return Ok(options.algorithm.solver()),
+	};
+	if table.goal != goal.flat {
+		return Err("The distance table was built for another goal !".into());
+	}
+	Ok(Box::new(TableSolver { table }))
+}
+
+fn build_table(file: &str, options: &Sia) -> Result<(), Box<dyn Error>> {
+	let goal = get_puzzle_goal(options, options.size);
+	let (table, histogram) = DistanceTable::build(&goal)?;
+
+	for (depth, count) in histogram.iter().enumerate() {
+		println!("{:>3}: {}", depth, count);
+	}
+	println!("Reachable states: {}", histogram.iter().sum::<usize>());
+	println!("Max depth: {}", histogram.len() - 1);
+	table.save(Path::new(file))
 }
 
 fn n_puzzle(options: &Sia) -> Result<(), Box<dyn Error>> {
+	if let Some(file) = &options.build_table {
+		return build_table(file, options);
+	}
 	let mut puzzle = get_puzzle(options)?;
-	let goal = get_puzzle_goal(options);
+	let spec = match &options.goal {
+		Some(f) => Some(get_goal_spec_from_file(f)?),
+		None => None,
+	};
+	let goal = match &spec {
+		Some(spec) => spec.representative(),
+		None => get_puzzle_goal(options, puzzle.n),
+	};
 
-	if !validity::check_puzzle(&puzzle, &goal) {
+	let valid = match &spec {
+		Some(spec) => validity::check_puzzle_spec(&puzzle, spec),
+		None => validity::check_puzzle(&puzzle, &goal),
+	};
+	if !valid {
 		return Err("Invalid puzzle !".into());
 	}
-	puzzle.set_goal(&goal);
+	match &spec {
+		Some(_) if !options.algorithm.searches_goal_spec() => {
+			return Err(
+				"Only ASTAR, GREEDY, UCS, WASTAR and IDASTAR search for a goal file !"
+					.into(),
+			);
+		}
+		Some(spec) => puzzle.set_goal_spec(spec),
+		None => puzzle.set_goal(&goal),
+	};
+
+	if options.portfolio {
+		return race(options, puzzle, &goal);
+	}
+	let mut progress: Box<dyn Progress> = if options.progress {
+		Box::new(StatusLine::new())
+	} else {
+		Box::new(Silent)
+	};
+	if options.hint {
+		return hint(options, &puzzle, &goal);
+	}
+	if options.all_optimal || options.count_optimal {
+		return all_optimal(options, puzzle, &goal, progress.as_mut());
+	}
+	if let Some(k) = options.k_shortest {
+		return shortest(options, puzzle, &goal, k, progress.as_mut());
+	}
+	if let (Algorithm::RealTime { rule, lookahead, trials }, None) =
+		(options.algorithm, &options.table)
+	{
+		let mut h = get_heuristic(options.heuristic);
+		let mut outcomes = real_time::trials(
+			puzzle,
+			&goal,
+			h.as_mut(),
+			rule,
+			lookahead,
+			trials,
+			&options.limits,
+			progress.as_mut(),
+		);
+		progress.finish();
+		for (i, outcome) in outcomes.iter().enumerate() {
+			if let Outcome::Solved(s) = outcome {
+				println!("Trial {}: {} moves", i + 1, s.moves.len());
+			}
+		}
+		report(options, outcomes.pop().unwrap());
+		return Ok(());
+	}
+	let mut solver = get_solver(options, &goal)?;
+	let outcome = solve(
+		solver.as_mut(),
+		puzzle,
+		&goal,
+		options.heuristic,
+		&options.limits,
+		progress.as_mut(),
+	);
 
-	Ok(solve(puzzle, goal, options))
+	report(options, outcome);
+	for line in solver.details() {
+		println!("{}", line);
+	}
+	Ok(())
+}
+
+fn report(options: &Sia, outcome: Outcome) {
+	match outcome {
+		Outcome::Solved(mut s) => {
+			if options.improve {
+				let before = s.moves.len();
+				s.moves =
+					improve(&s.start, &s.moves, DEFAULT_WINDOW, &options.limits);
+				println!("Moves before improvement: {}", before);
+			}
+			print!("{}", s)
+		}
+		Outcome::Unsolvable(_) => println!("No solution !"),
+		Outcome::Interrupted(stats) => println!(
+			"Gave up after opening {} states !",
+			stats.total_opened
+		),
+	};
+}
+
+fn all_optimal(
+	options: &Sia,
+	puzzle: Puzzle,
+	goal: &Puzzle,
+	progress: &mut dyn Progress,
+) -> Result<(), Box<dyn Error>> {
+	let mut h = get_heuristic(options.heuristic);
+	let optimal = enumerate::optimal_solutions(
+		puzzle,
+		goal,
+		h.as_mut(),
+		options.count_optimal,
+		&options.limits,
+		progress,
+	);
+	progress.finish();
+
+	match optimal {
+		Ok(optimal) => {
+			for (i, moves) in optimal.solutions.iter().enumerate() {
+				println!("{:>4}: {}", i + 1, letters(moves));
+			}
+			println!("Total opened: {}", optimal.stats.total_opened);
+			println!("Number of moves: {}", optimal.length);
+			println!("Optimal solutions: {}", optimal.count);
+		}
+		Err(outcome) => report(options, outcome),
+	};
+	Ok(())
+}
+
+fn shortest(
+	options: &Sia,
+	puzzle: Puzzle,
+	goal: &Puzzle,
+	k: usize,
+	progress: &mut dyn Progress,
+) -> Result<(), Box<dyn Error>> {
+	let mut h = get_heuristic(options.heuristic);
+	let mut outcomes = k_shortest::k_shortest(
+		puzzle,
+		goal,
+		h.as_mut(),
+		k,
+		&options.limits,
+		progress,
+	);
+	progress.finish();
+
+	let stopped = match outcomes.last() {
+		Some(Outcome::Solved(_)) => None,
+		_ => outcomes.pop(),
+	};
+	for (i, outcome) in outcomes.iter().enumerate() {
+		if let Outcome::Solved(s) = outcome {
+			let (length, moves) = (s.moves.len(), letters(&s.moves));
+			println!("{:>4}: {} moves {}", i + 1, length, moves);
+		}
+	}
+	if let Some(outcome) = stopped {
+		report(options, outcome);
+	}
+	Ok(())
+}
+
+fn hint(
+	options: &Sia,
+	puzzle: &Puzzle,
+	goal: &Puzzle,
+) -> Result<(), Box<dyn Error>> {
+	let mut hinter = match &options.pdb {
+		Some(f) => Hinter::stored(goal, Path::new(f))?,
+		None => Hinter::new(goal),
+	};
+	match hinter.hint(puzzle, &options.limits) {
+		Ok(hint) => {
+			println!("Total opened: {}", hint.stats.total_opened);
+			println!("Remaining moves: {}", hint.distance);
+			match hint.moves.first() {
+				Some(m) => {
+					println!("Best move: {:?}", m);
+					println!("Optimal moves: {}", letters(&hint.moves));
+				}
+				None => println!("Already solved !"),
+			}
+		}
+		Err(outcome) => report(options, outcome),
+	};
+	Ok(())
+}
+
+// One letter per move, for the listings of several solutions
+//
+fn letters(moves: &[Move]) -> String {
+	moves
+		.iter()
+		.map(|m| format!("{:?}", m).remove(0))
+		.collect::<String>()
+}
+
+fn race(
+	options: &Sia,
+	puzzle: Puzzle,
+	goal: &Puzzle,
+) -> Result<(), Box<dyn Error>> {
+	let contenders = portfolio::default_contenders();
+	let race = portfolio::race(
+		&contenders,
+		&puzzle,
+		goal,
+		&options.limits,
+		options.budget,
+	);
+
+	match race {
+		Some(race) => {
+			println!("Portfolio winner: {}", race.winner.name());
+			report(options, race.outcome);
+		}
+		None => println!("No answer from the portfolio !"),
+	};
+	Ok(())
 }
 
 fn main() {
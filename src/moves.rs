@@ -1,4 +1,5 @@
 use super::puzzle::Puzzle;
+use super::zobrist;
 
 #[derive(Clone, Copy, Hash, PartialEq, Debug)]
 pub enum Move {
@@ -45,6 +46,8 @@ impl Move {
 
     pub fn apply(&self, puzzle: &mut Puzzle) {
         let swap_blank = |puzzle: &mut Puzzle, idx: usize| {
+            let tile = puzzle.flat[idx];
+            puzzle.key ^= zobrist::slide(tile, idx, puzzle.blank);
             puzzle.flat.swap(puzzle.blank, idx);
             puzzle.blank = idx;
         };
@@ -4,6 +4,7 @@ use std::io::{self, BufRead, BufReader};
 use std::num::ParseIntError;
 use std::path::Path;
 
+use super::goal::{GoalSpec, Pattern};
 use super::puzzle::Matrix;
 
 fn read_file(path: &Path) -> io::Result<Vec<String>> {
@@ -47,6 +48,43 @@ pub fn parse_puzzle(
 	Ok((msize, matrix))
 }
 
+fn parse_patterns(
+	lines: &[String],
+) -> Result<Vec<Pattern>, ParseIntError> {
+	lines
+		.iter()
+		.map(|line| {
+			line.split_whitespace()
+				.map(|cell| match cell {
+					"_" => Ok(None),
+					number => number.parse::<u16>().map(Some),
+				})
+				.collect::<Result<Pattern, _>>()
+		})
+		.collect::<Result<Vec<_>, _>>()
+}
+
+// A goal file is written like a puzzle file, with `_` for a free cell.
+// Several boards one after the other are alternative goals.
+//
+pub fn parse_goal(path: &Path) -> Result<GoalSpec, Box<dyn Error>> {
+	let file_lines = read_file(path)?;
+	let goal_maybe = sanitize_comments(&file_lines);
+	let first = goal_maybe.first().ok_or("The goal file is empty !")?;
+	let msize = parse_matrix_size(first)?;
+	let rows = parse_patterns(&goal_maybe[1..])?;
+	if msize == 0
+		|| rows.is_empty()
+		|| rows.len() % msize != 0
+		|| rows.iter().any(|row| row.len() != msize)
+	{
+		return Err("The goal boards have the wrong size !".into());
+	}
+	let patterns =
+		rows.chunks(msize).map(|board| board.concat()).collect();
+	GoalSpec::new(msize, patterns)
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -99,4 +137,27 @@ mod tests {
 			)
 		);
 	}
+
+	#[test]
+	fn test_goal_with_free_cells() {
+		let path = Path::new("./puzzles/goal-top-row.txt");
+		let spec = parse_goal(path).unwrap();
+		assert_eq!(spec.n, 4);
+		assert_eq!(spec.patterns.len(), 2);
+		assert_eq!(
+			spec.patterns[0][..4],
+			[Some(1), Some(2), Some(3), Some(4)]
+		);
+		assert!(spec.patterns[0][4..].iter().all(Option::is_none));
+		assert_eq!(
+			spec.patterns[1][12..],
+			[Some(1), Some(2), Some(3), Some(4)]
+		);
+	}
+
+	#[test]
+	fn test_empty_goal() {
+		let path = Path::new("./puzzles/goal-empty.txt");
+		assert!(parse_goal(path).is_err());
+	}
 }
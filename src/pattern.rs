@@ -0,0 +1,310 @@
+use super::heuristics::Heuristic;
+use super::puzzle::Puzzle;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+const MAGIC: &[u8; 4] = b"NPDB";
+
+// Most tiles in one group, a group of k tiles on c cells has a table of
+// c^k entries and its search c^(k + 1) states
+//
+pub const GROUP_SIZE: usize = 5;
+
+// Databases built so far in this process with their goal, a 4x4 one takes
+// seconds to build and every search for the same goal can share it
+//
+static SHARED: Mutex<Vec<(Vec<u16>, PatternDatabase)>> =
+    Mutex::new(vec![]);
+
+// [https://arxiv.org/abs/1107.0050]
+//
+// Additive pattern database: the tiles are split into disjoint groups and
+// the table of a group gives the fewest moves of its own tiles needed to
+// bring them home, wherever the other tiles are. Only the moves of the
+// group are counted so the tables of all the groups add up to a lower
+// bound much closer than the Manhattan distance.
+//
+#[derive(Clone)]
+pub struct PatternDatabase {
+    cells: usize,
+    // group and place in that group of every tile, None for the blank
+    slots: Vec<Option<(usize, u32)>>,
+    tables: Arc<Vec<Vec<u8>>>,
+}
+
+impl PatternDatabase {
+    pub fn build(goal: &Puzzle) -> PatternDatabase {
+        let (slots, groups) = split(goal);
+        let tables = groups
+            .iter()
+            .map(|group| build_group(goal, group))
+            .collect();
+        PatternDatabase {
+            cells: goal.flat.len(),
+            slots,
+            tables: Arc::new(tables),
+        }
+    }
+
+    // The database of the goal built the first time it is asked for, the
+    // same tables after that
+    //
+    pub fn shared(goal: &Puzzle) -> PatternDatabase {
+        let mut shared = SHARED.lock().unwrap();
+        if let Some((_, pdb)) =
+            shared.iter().find(|(g, _)| *g == goal.flat)
+        {
+            return pdb.clone();
+        }
+        let pdb = PatternDatabase::build(goal);
+        shared.push((goal.flat.clone(), pdb.clone()));
+        pdb
+    }
+
+    // Same as shared, but kept in a file: read from it when it exists,
+    // written to it once built otherwise
+    //
+    pub fn stored(
+        goal: &Puzzle,
+        path: &Path,
+    ) -> Result<PatternDatabase, Box<dyn Error>> {
+        if !path.exists() {
+            let pdb = PatternDatabase::shared(goal);
+            pdb.save(goal, path)?;
+            return Ok(pdb);
+        }
+        let pdb = PatternDatabase::load(goal, path)?;
+        let mut shared = SHARED.lock().unwrap();
+        shared.retain(|(g, _)| *g != goal.flat);
+        shared.push((goal.flat.clone(), pdb.clone()));
+        Ok(pdb)
+    }
+
+    pub fn save(
+        &self,
+        goal: &Puzzle,
+        path: &Path,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(goal.n as u8);
+        bytes.extend(goal.flat.iter().map(|&t| t as u8));
+        for table in self.tables.iter() {
+            bytes.extend(table);
+        }
+        fs::write(path, bytes)?;
+        Ok(())
+    }
+
+    pub fn load(
+        goal: &Puzzle,
+        path: &Path,
+    ) -> Result<PatternDatabase, Box<dyn Error>> {
+        let bytes = fs::read(path)?;
+        let cells = goal.flat.len();
+        let header = 5 + cells;
+        if bytes.len() < header || &bytes[..4] != MAGIC {
+            return Err("Invalid pattern database file !".into());
+        }
+        let built_for = bytes[5..header].iter().map(|&t| t as u16);
+        if bytes[4] as usize != goal.n || !built_for.eq(goal.flat.clone())
+        {
+            return Err(
+                "The pattern database was built for another goal !".into(),
+            );
+        }
+        let (slots, groups) = split(goal);
+        let sizes = groups
+            .iter()
+            .map(|group| cells.pow(group.len() as u32))
+            .collect::<Vec<_>>();
+        if bytes.len() != header + sizes.iter().sum::<usize>() {
+            return Err("Invalid pattern database file !".into());
+        }
+        let mut offset = header;
+        let tables = sizes
+            .iter()
+            .map(|size| {
+                offset += size;
+                bytes[offset - size..offset].to_vec()
+            })
+            .collect();
+        Ok(PatternDatabase {
+            cells,
+            slots,
+            tables: Arc::new(tables),
+        })
+    }
+}
+
+// The groups of tiles of the goal, and the group and place in that group
+// of every tile
+//
+#[allow(clippy::type_complexity)]
+fn split(goal: &Puzzle) -> (Vec<Option<(usize, u32)>>, Vec<Vec<u16>>) {
+    let tiles = goal
+        .flat
+        .iter()
+        .cloned()
+        .filter(|&t| t != 0)
+        .collect::<Vec<_>>();
+    let count = tiles.len().div_ceil(GROUP_SIZE).max(1);
+    let size = tiles.len().div_ceil(count).max(1);
+
+    let mut slots = vec![None; goal.flat.len()];
+    let groups = tiles.chunks(size).map(|group| group.to_vec()).collect();
+    for (g, group) in tiles.chunks(size).enumerate() {
+        for (s, &tile) in group.iter().enumerate() {
+            slots[tile as usize] = Some((g, s as u32));
+        }
+    }
+    (slots, groups)
+}
+
+// 0-1 breadth-first search backwards from the goal, over the cells of the
+// tiles of the group and of the blank. The blank moves for free unless it
+// swaps with one of the group.
+//
+fn build_group(goal: &Puzzle, group: &[u16]) -> Vec<u8> {
+    let n = goal.n;
+    let cells = goal.flat.len();
+    let size = cells.pow(group.len() as u32);
+    let mut table = vec![u8::MAX; size];
+    let mut distance = vec![u8::MAX; size * cells];
+
+    let encode = |positions: &[usize]| {
+        positions
+            .iter()
+            .rev()
+            .fold(0, |index, &p| index * cells + p)
+    };
+    let decode = |mut index: usize, positions: &mut Vec<usize>| {
+        positions.clear();
+        for _ in 0..group.len() {
+            positions.push(index % cells);
+            index /= cells;
+        }
+    };
+
+    let home = group
+        .iter()
+        .map(|&t| Puzzle::get_index_of(&goal.flat, t))
+        .collect::<Vec<_>>();
+    let start = goal.blank * size + encode(&home);
+    distance[start] = 0;
+    let mut queue = VecDeque::new();
+    queue.push_back(start);
+    let mut positions = vec![];
+
+    while let Some(state) = queue.pop_front() {
+        let d = distance[state];
+        let (blank, pattern) = (state / size, state % size);
+        table[pattern] = table[pattern].min(d);
+        decode(pattern, &mut positions);
+
+        let (row, column) = (blank / n, blank % n);
+        let mut neighbors = Vec::with_capacity(4);
+        if row > 0 {
+            neighbors.push(blank - n);
+        }
+        if row + 1 < n {
+            neighbors.push(blank + n);
+        }
+        if column > 0 {
+            neighbors.push(blank - 1);
+        }
+        if column + 1 < n {
+            neighbors.push(blank + 1);
+        }
+        for next in neighbors {
+            let (child, cost) =
+                match positions.iter().position(|&p| p == next) {
+                    Some(i) => {
+                        let mut moved = positions.clone();
+                        moved[i] = blank;
+                        (next * size + encode(&moved), d + 1)
+                    }
+                    None => (next * size + pattern, d),
+                };
+            if cost >= distance[child] {
+                continue;
+            }
+            distance[child] = cost;
+            if cost == d {
+                queue.push_front(child);
+            } else {
+                queue.push_back(child);
+            }
+        }
+    }
+    table
+}
+
+impl Heuristic for PatternDatabase {
+    fn first_time(&mut self, p1: &Puzzle, _: &Puzzle) -> u32 {
+        let mut indexes = vec![0; self.tables.len()];
+        for (cell, &tile) in p1.flat.iter().enumerate() {
+            if let Some((g, s)) = self.slots[tile as usize] {
+                indexes[g] += cell * self.cells.pow(s);
+            }
+        }
+        indexes
+            .iter()
+            .zip(self.tables.iter())
+            .map(|(&i, table)| table[i] as u32)
+            .sum()
+    }
+
+    fn fork(&self) -> Box<dyn Heuristic + Send> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::heuristics::{get_heuristic, HeuristicFunc};
+    use crate::puzzle::{Difficulty, PuzzleKind};
+    use crate::table::DistanceTable;
+
+    #[test]
+    fn test_between_manhattan_and_exact() {
+        let goal = Puzzle::new(PuzzleKind::Snail, 3);
+        let mut pdb = PatternDatabase::build(&goal);
+        let mut manhattan =
+            get_heuristic(HeuristicFunc::ManhattanDistance);
+        let (table, _) = DistanceTable::build(&goal).unwrap();
+
+        assert_eq!(pdb.first_time(&goal, &goal), 0);
+        for _ in 0..50 {
+            let mut start = Puzzle::new_randomized(
+                PuzzleKind::Snail,
+                Difficulty::Medium,
+                3,
+            );
+            start.set_goal(&goal);
+            let h = pdb.first_time(&start, &goal);
+            let (path, _) = table.solve(&start).unwrap();
+            assert!(h >= manhattan.first_time(&start, &goal));
+            assert!((h as usize) < path.len());
+        }
+    }
+
+    #[test]
+    fn test_save_load() {
+        let goal = Puzzle::new(PuzzleKind::Snail, 3);
+        let pdb = PatternDatabase::build(&goal);
+        let path = std::env::temp_dir().join("n-puzzle-pdb-3x3");
+        pdb.save(&goal, &path).unwrap();
+        let loaded = PatternDatabase::load(&goal, &path).unwrap();
+        let other = Puzzle::new(PuzzleKind::Classic, 3);
+        let wrong_goal = PatternDatabase::load(&other, &path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.slots, pdb.slots);
+        assert_eq!(loaded.tables, pdb.tables);
+        assert!(wrong_goal.is_err());
+    }
+}
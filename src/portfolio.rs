@@ -0,0 +1,216 @@
+use super::bucket::TieBreak;
+use super::heuristics::HeuristicFunc;
+use super::progress::Silent;
+use super::puzzle::Puzzle;
+use super::solution::{solve, Algorithm};
+use super::solver::{Limits, Outcome};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// One (algorithm, heuristic) pair of a portfolio
+//
+#[derive(Clone, Copy, Debug)]
+pub struct Contender {
+    pub algorithm: Algorithm,
+    pub heuristic: HeuristicFunc,
+}
+
+impl Contender {
+    pub fn name(&self) -> String {
+        let solver = self.algorithm.solver();
+        match self.algorithm {
+            Algorithm::WeightedAStar { weight, .. } => {
+                format!(
+                    "{} (w = {}) + {:?}",
+                    solver.name(),
+                    weight,
+                    self.heuristic
+                )
+            }
+            _ => format!("{} + {:?}", solver.name(), self.heuristic),
+        }
+    }
+}
+
+// Pairs that do well on different kinds of boards: the fast optimal
+// searches and a weighted one that gives an answer early on hard boards
+//
+pub fn default_contenders() -> Vec<Contender> {
+    vec![
+        Contender {
+            algorithm: Algorithm::IDAStar { table_size: 0 },
+            heuristic: HeuristicFunc::LinearConflicts,
+        },
+        Contender {
+            algorithm: Algorithm::AStar {
+                tie_break: TieBreak::HighG,
+            },
+            heuristic: HeuristicFunc::ManhattanDistance,
+        },
+        Contender {
+            algorithm: Algorithm::WeightedAStar {
+                weight: 2,
+                tie_break: TieBreak::HighG,
+            },
+            heuristic: HeuristicFunc::LinearConflicts,
+        },
+    ]
+}
+
+pub struct Race {
+    pub winner: Contender,
+    pub outcome: Outcome,
+}
+
+// Runs every contender on its own thread. The first optimal answer wins
+// right away, otherwise the shortest solution found before the budget
+// runs out or everyone finished. The others are cancelled.
+//
+pub fn race(
+    contenders: &[Contender],
+    start: &Puzzle,
+    end: &Puzzle,
+    limits: &Limits,
+    budget: Option<Duration>,
+) -> Option<Race> {
+    let cancel = Arc::new(AtomicBool::new(false));
+    let limits = Limits {
+        cancel: Some(cancel.clone()),
+        ..limits.clone()
+    };
+    let deadline = budget.map(|budget| Instant::now() + budget);
+
+    let best = thread::scope(|scope| {
+        let (sender, receiver) = mpsc::channel();
+        for (i, contender) in contenders.iter().enumerate() {
+            let sender = sender.clone();
+            let limits = limits.clone();
+            let start = start.clone();
+            scope.spawn(move || {
+                let mut solver = contender.algorithm.solver();
+                let outcome = solve(
+                    solver.as_mut(),
+                    start,
+                    end,
+                    contender.heuristic,
+                    &limits,
+                    &mut Silent,
+                );
+                let _ = sender.send((i, outcome));
+            });
+        }
+        drop(sender);
+
+        let mut best: Option<(usize, Outcome)> = None;
+        loop {
+            let received = match deadline {
+                Some(deadline) => receiver.recv_timeout(
+                    deadline.saturating_duration_since(Instant::now()),
+                ),
+                None => receiver
+                    .recv()
+                    .map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            let (i, outcome) = match received {
+                Ok(received) => received,
+                Err(_) => break,
+            };
+            match &outcome {
+                Outcome::Solved(solution)
+                    if contenders[i]
+                        .algorithm
+                        .is_optimal(&solution.stats) =>
+                {
+                    best = Some((i, outcome));
+                    break;
+                }
+                // no other contender can do better than a proof
+                Outcome::Unsolvable(stats)
+                    if contenders[i].algorithm.is_optimal(stats) =>
+                {
+                    best = Some((i, outcome));
+                    break;
+                }
+                Outcome::Solved(solution) => {
+                    let shorter = match &best {
+                        Some((_, Outcome::Solved(b))) => {
+                            solution.moves.len() < b.moves.len()
+                        }
+                        _ => true,
+                    };
+                    if shorter {
+                        best = Some((i, outcome));
+                    }
+                }
+                // a search that can miss solutions proves nothing
+                Outcome::Unsolvable(_) | Outcome::Interrupted(_) => (),
+            }
+        }
+        cancel.store(true, Ordering::Relaxed);
+        best
+    });
+
+    best.map(|(i, outcome)| Race {
+        winner: contenders[i],
+        outcome,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::hardest_classic;
+
+    #[test]
+    fn test_first_optimal_wins() {
+        let (start, goal) = hardest_classic();
+        let race = race(
+            &default_contenders(),
+            &start,
+            &goal,
+            &Limits::default(),
+            None,
+        )
+        .unwrap();
+
+        assert!(race.winner.algorithm.is_optimal(&race.outcome.stats()));
+        assert_eq!(race.outcome.solution().unwrap().moves.len(), 31);
+    }
+
+    #[test]
+    fn test_budget_cancels() {
+        let (start, goal) = hardest_classic();
+        let contenders = [
+            Contender {
+                algorithm: Algorithm::IDAStar { table_size: 0 },
+                heuristic: HeuristicFunc::Zero,
+            },
+            Contender {
+                algorithm: Algorithm::WeightedAStar {
+                    weight: 3,
+                    tie_break: TieBreak::HighG,
+                },
+                heuristic: HeuristicFunc::ManhattanDistance,
+            },
+        ];
+        let started = Instant::now();
+        let race = race(
+            &contenders,
+            &start,
+            &goal,
+            &Limits::default(),
+            Some(Duration::from_millis(300)),
+        )
+        .unwrap();
+
+        assert!(started.elapsed() < Duration::from_secs(10));
+        assert_eq!(
+            race.winner.name(),
+            "Weighted A* (w = 3) + ManhattanDistance"
+        );
+        assert!(race.outcome.solution().unwrap().moves.len() >= 31);
+    }
+}
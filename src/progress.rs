@@ -0,0 +1,214 @@
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+// How often the best-first searches are allowed to report
+//
+pub const REPORT_INTERVAL: Duration = Duration::from_millis(250);
+
+// Checking the clock on every expansion is too expensive,
+// so the searches only look at it every few nodes
+//
+const CLOCK_STRIDE: usize = 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Status {
+    Iteration {
+        bound: u32,
+        nodes: usize,
+    },
+    Frontier {
+        open: usize,
+        closed: usize,
+        min_f: u32,
+        rate: f64,
+    },
+    Layer {
+        depth: usize,
+        kept: usize,
+        min_h: u32,
+        rate: f64,
+    },
+    Trial {
+        trial: usize,
+        moves: usize,
+        nodes: usize,
+    },
+}
+
+pub trait Progress {
+    fn report(&mut self, status: &Status);
+    fn finish(&mut self) {}
+}
+
+pub struct Silent;
+
+impl Progress for Silent {
+    fn report(&mut self, _: &Status) {}
+}
+
+#[derive(Default)]
+pub struct StatusLine {
+    width: usize,
+}
+
+impl StatusLine {
+    pub fn new() -> StatusLine {
+        StatusLine { width: 0 }
+    }
+}
+
+impl Progress for StatusLine {
+    fn report(&mut self, status: &Status) {
+        let line = status.to_string();
+        let pad = self.width.saturating_sub(line.len());
+        self.width = line.len();
+
+        let mut stderr = io::stderr();
+        let _ = write!(stderr, "\r{}{:pad$}", line, "", pad = pad);
+        let _ = stderr.flush();
+    }
+
+    fn finish(&mut self) {
+        if self.width > 0 {
+            eprintln!();
+            self.width = 0;
+        }
+    }
+}
+
+use std::fmt;
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Status::Iteration { bound, nodes } => {
+                write!(f, "bound: {} | nodes: {}", bound, nodes)
+            }
+            Status::Frontier {
+                open,
+                closed,
+                min_f,
+                rate,
+            } => write!(
+                f,
+                "open: {} | closed: {} | min f: {} | {:.0} nodes/s",
+                open, closed, min_f, rate
+            ),
+            Status::Layer {
+                depth,
+                kept,
+                min_h,
+                rate,
+            } => write!(
+                f,
+                "depth: {} | kept: {} | min h: {} | {:.0} nodes/s",
+                depth, kept, min_h, rate
+            ),
+            Status::Trial {
+                trial,
+                moves,
+                nodes,
+            } => write!(
+                f,
+                "trial: {} | moves: {} | nodes: {}",
+                trial, moves, nodes
+            ),
+        }
+    }
+}
+
+// Tells a search when it is time to report again
+//
+pub struct Ticker {
+    start: Instant,
+    last: Instant,
+    ticks: usize,
+}
+
+impl Ticker {
+    pub fn new() -> Ticker {
+        let now = Instant::now();
+        Ticker {
+            start: now,
+            last: now,
+            ticks: 0,
+        }
+    }
+
+    pub fn tick(&mut self) -> bool {
+        self.ticks += 1;
+        if self.ticks % CLOCK_STRIDE != 0 {
+            return false;
+        }
+        let now = Instant::now();
+        if now.duration_since(self.last) < REPORT_INTERVAL {
+            return false;
+        }
+        self.last = now;
+        true
+    }
+
+    pub fn rate(&self) -> f64 {
+        let elapsed = self.start.elapsed().as_secs_f64();
+        if elapsed > 0.0 {
+            self.ticks as f64 / elapsed
+        } else {
+            0.0
+        }
+    }
+}
+
+impl Default for Ticker {
+    fn default() -> Ticker {
+        Ticker::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_display() {
+        let iteration = Status::Iteration {
+            bound: 12,
+            nodes: 345,
+        };
+        let frontier = Status::Frontier {
+            open: 10,
+            closed: 20,
+            min_f: 30,
+            rate: 1234.4,
+        };
+        let layer = Status::Layer {
+            depth: 7,
+            kept: 100,
+            min_h: 42,
+            rate: 99.6,
+        };
+        let trial = Status::Trial {
+            trial: 3,
+            moves: 21,
+            nodes: 500,
+        };
+
+        assert_eq!(iteration.to_string(), "bound: 12 | nodes: 345");
+        assert_eq!(
+            frontier.to_string(),
+            "open: 10 | closed: 20 | min f: 30 | 1234 nodes/s"
+        );
+        assert_eq!(
+            layer.to_string(),
+            "depth: 7 | kept: 100 | min h: 42 | 100 nodes/s"
+        );
+        assert_eq!(trial.to_string(), "trial: 3 | moves: 21 | nodes: 500");
+    }
+
+    #[test]
+    fn test_ticker_stride() {
+        let mut ticker = Ticker::new();
+        for _ in 1..CLOCK_STRIDE {
+            assert!(!ticker.tick());
+        }
+    }
+}
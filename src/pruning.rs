@@ -0,0 +1,314 @@
+use super::moves::Move;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::OnceLock;
+
+// The number of strings grows by three at each move, past 8 building the
+// machine takes longer than most searches. Longer loops, like turning three
+// times around a 2x2 square, are left to the on-path check.
+//
+pub const DEFAULT_DEPTH: usize = 8;
+
+const MOVES: [Move; 4] = [Move::Up, Move::Down, Move::Left, Move::Right];
+
+type Cell = (i32, i32);
+
+fn step(cell: Cell, m: usize) -> Cell {
+    match MOVES[m] {
+        Move::Up => (cell.0 - 1, cell.1),
+        Move::Down => (cell.0 + 1, cell.1),
+        Move::Left => (cell.0, cell.1 - 1),
+        _ => (cell.0, cell.1 + 1),
+    }
+}
+
+fn opposite(m: usize) -> usize {
+    m ^ 1
+}
+
+// What a sequence of moves does on a board big enough for it: where the
+// blank ends up and where each tile it displaced came from
+//
+// Both lists are kept sorted, they hold at most one entry per move
+//
+#[derive(Clone)]
+struct Effect {
+    blank: Cell,
+    tiles: Vec<(Cell, Cell)>,
+    visited: Vec<Cell>,
+}
+
+impl Effect {
+    fn new() -> Effect {
+        Effect {
+            blank: (0, 0),
+            tiles: vec![],
+            visited: vec![(0, 0)],
+        }
+    }
+
+    fn apply(&self, m: usize) -> Effect {
+        let mut next = self.clone();
+        let cell = step(self.blank, m);
+        let tile = match next.tiles.binary_search_by_key(&cell, |t| t.0) {
+            Ok(i) => next.tiles.remove(i).1,
+            Err(_) => cell,
+        };
+        if tile != self.blank {
+            let i = next
+                .tiles
+                .binary_search_by_key(&self.blank, |t| t.0)
+                .unwrap_err();
+            next.tiles.insert(i, (self.blank, tile));
+        }
+        next.blank = cell;
+        if let Err(i) = next.visited.binary_search(&cell) {
+            next.visited.insert(i, cell);
+        }
+        next
+    }
+
+    fn key(&self) -> (Cell, Vec<(Cell, Cell)>) {
+        (self.blank, self.tiles.clone())
+    }
+
+    fn visits_all(&self, cells: &[Cell]) -> bool {
+        cells.iter().all(|c| self.visited.binary_search(c).is_ok())
+    }
+}
+
+// [https://www.aaai.org/Papers/AAAI/1993/AAAI93-115.pdf]
+//
+// Finite state machine over the moves made so far that refuses the move
+// sequences known to do what a shorter sequence, or one of the same length
+// that comes first, already does. Those are found by a breadth-first search
+// over move strings up to a depth, and turned into an Aho-Corasick automaton
+// so checking a move is a single lookup.
+//
+// A string is only refused when its duplicate moves the blank through a
+// subset of the cells it does: whenever the refused one fits on the board,
+// its duplicate fits too and no board becomes unreachable.
+//
+pub struct MovePruning {
+    next: Vec<[Option<u32>; 4]>,
+    pub forbidden: usize,
+}
+
+impl MovePruning {
+    pub fn new(depth: usize) -> MovePruning {
+        let forbidden = MovePruning::duplicates(depth);
+        let mut pruning = MovePruning::automaton(&forbidden);
+        pruning.forbidden = forbidden.len();
+        pruning
+    }
+
+    // The one every IDA* search shares, it does not depend on the board
+    //
+    pub fn shared() -> &'static MovePruning {
+        static SHARED: OnceLock<MovePruning> = OnceLock::new();
+        SHARED.get_or_init(|| MovePruning::new(DEFAULT_DEPTH))
+    }
+
+    pub fn start(&self) -> usize {
+        0
+    }
+
+    pub fn len(&self) -> usize {
+        self.next.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.next.is_empty()
+    }
+
+    // The state after making `m`, none if that move must be pruned
+    //
+    pub fn next(&self, state: usize, m: Move) -> Option<usize> {
+        let m = MOVES.iter().position(|&x| x == m)?;
+        self.next[state][m].map(|s| s as usize)
+    }
+
+    fn is_clean(
+        string: &[usize],
+        forbidden: &HashSet<Vec<usize>>,
+    ) -> bool {
+        (0..string.len()).all(|i| !forbidden.contains(&string[i..]))
+    }
+
+    fn duplicates(depth: usize) -> HashSet<Vec<usize>> {
+        let mut forbidden: HashSet<Vec<usize>> =
+            (0..4).map(|m| vec![m, opposite(m)]).collect();
+        let mut seen = HashMap::new();
+        seen.insert(Effect::new().key(), Effect::new().visited);
+        let mut layer = vec![(vec![], Effect::new())];
+        for _ in 0..depth {
+            let mut next = vec![];
+            // parents and moves are in order, so is each layer
+            for (string, effect) in &layer {
+                for m in 0..4 {
+                    let mut longer: Vec<usize> = string.clone();
+                    longer.push(m);
+                    if !MovePruning::is_clean(&longer, &forbidden) {
+                        continue;
+                    }
+                    let effect = effect.apply(m);
+                    let key = effect.key();
+                    match seen.get(&key) {
+                        Some(first) if effect.visits_all(first) => {
+                            forbidden.insert(longer);
+                        }
+                        Some(_) => next.push((longer, effect)),
+                        None => {
+                            seen.insert(key, effect.visited.clone());
+                            next.push((longer, effect));
+                        }
+                    }
+                }
+            }
+            layer = next;
+        }
+        forbidden
+    }
+
+    // [https://en.wikipedia.org/wiki/Aho%E2%80%93Corasick_algorithm]
+    //
+    fn automaton(forbidden: &HashSet<Vec<usize>>) -> MovePruning {
+        let mut trie: Vec<[Option<u32>; 4]> = vec![[None; 4]];
+        let mut dead = vec![false];
+        for string in forbidden {
+            let mut state = 0;
+            for &m in string {
+                state = match trie[state][m] {
+                    Some(s) => s as usize,
+                    None => {
+                        trie.push([None; 4]);
+                        dead.push(false);
+                        trie[state][m] = Some(trie.len() as u32 - 1);
+                        trie.len() - 1
+                    }
+                };
+            }
+            dead[state] = true;
+        }
+
+        // goto function completed with the failure links, breadth first
+        let mut next = vec![[0u32; 4]; trie.len()];
+        let mut fail = vec![0; trie.len()];
+        let mut queue = VecDeque::new();
+        for m in 0..4 {
+            match trie[0][m] {
+                Some(s) => {
+                    next[0][m] = s;
+                    queue.push_back(s as usize);
+                }
+                None => next[0][m] = 0,
+            }
+        }
+        while let Some(state) = queue.pop_front() {
+            dead[state] |= dead[fail[state]];
+            for m in 0..4 {
+                match trie[state][m] {
+                    Some(s) => {
+                        fail[s as usize] = next[fail[state]][m] as usize;
+                        next[state][m] = s;
+                        queue.push_back(s as usize);
+                    }
+                    None => next[state][m] = next[fail[state]][m],
+                }
+            }
+        }
+
+        MovePruning {
+            next: next
+                .iter()
+                .map(|row| {
+                    let mut pruned = [None; 4];
+                    for m in 0..4 {
+                        if !dead[row[m] as usize] {
+                            pruned[m] = Some(row[m]);
+                        }
+                    }
+                    pruned
+                })
+                .collect(),
+            forbidden: forbidden.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::{Puzzle, PuzzleKind};
+
+    fn accepts(pruning: &MovePruning, moves: &[Move]) -> bool {
+        let mut state = pruning.start();
+        moves.iter().all(|&m| match pruning.next(state, m) {
+            Some(s) => {
+                state = s;
+                true
+            }
+            None => false,
+        })
+    }
+
+    #[test]
+    fn test_prunes_reverse_and_loops() {
+        let pruning = MovePruning::shared();
+        let (u, d, l, r) = (Move::Up, Move::Down, Move::Left, Move::Right);
+
+        assert!(!accepts(pruning, &[l, r]));
+        assert!(!accepts(pruning, &[u, d, l]));
+        // one and a half turn around a 2x2 square, either way
+        assert!(accepts(pruning, &[u, l, d, r, u, l]));
+        assert!(!accepts(pruning, &[l, u, r, d, l, u]));
+        assert!(!accepts(pruning, &[u, l, d, r, u, l, d, r, u, l, d, r]));
+    }
+
+    fn walk(
+        p: &mut Puzzle,
+        state: usize,
+        depth: usize,
+        found: &mut HashMap<Vec<u16>, usize>,
+    ) {
+        let best = found.entry(p.flat.clone()).or_insert(depth);
+        *best = depth.min(*best);
+        if depth == 10 {
+            return;
+        }
+        for m in Move::moves(p) {
+            if let Some(next) = MovePruning::shared().next(state, m) {
+                let was = p.was;
+                m.apply(p);
+                walk(p, next, depth + 1, found);
+                m.opposite().apply(p);
+                p.was = was;
+            }
+        }
+    }
+
+    #[test]
+    fn test_keeps_shortest_paths() {
+        // the boards up to 10 moves away are all reached, as fast as with
+        // every move allowed
+        let start = Puzzle::new(PuzzleKind::Snail, 3);
+        let mut found = HashMap::new();
+        walk(&mut start.clone(), 0, 0, &mut found);
+
+        let mut depth = HashMap::new();
+        depth.insert(start.flat.clone(), 0);
+        let mut layer = vec![start];
+        for d in 1..=10 {
+            let mut next = vec![];
+            for p in layer {
+                for q in p.neighbors() {
+                    if !depth.contains_key(&q.flat) {
+                        depth.insert(q.flat.clone(), d);
+                        next.push(q);
+                    }
+                }
+            }
+            layer = next;
+        }
+        assert_eq!(found, depth);
+    }
+}
@@ -1,5 +1,9 @@
 use super::generate;
+use super::goal::{Goal, GoalSpec};
 use super::moves::Move;
+use super::zobrist;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
 
 pub type Matrix = Vec<Vec<u16>>;
 
@@ -17,13 +21,15 @@ pub enum Difficulty {
     Hard,
 }
 
-#[derive(Clone, Hash)]
+#[derive(Clone)]
 pub struct Puzzle {
     pub n: usize,
     pub flat: Vec<u16>,
-    pub end: Vec<usize>,
+    pub end: Arc<Goal>,
     pub blank: usize,
     pub was: Move,
+    // zobrist hash of flat, kept up to date by Move::apply
+    pub key: u64,
 }
 
 impl Puzzle {
@@ -36,14 +42,19 @@ impl Puzzle {
     pub fn from_matrix(msize: usize, matrix: Matrix) -> Puzzle {
         let flat =
             matrix.iter().flat_map(|row| row.iter()).cloned().collect();
+        Puzzle::from_flat(msize, flat)
+    }
+
+    pub fn from_flat(msize: usize, flat: Vec<u16>) -> Puzzle {
         let blank = Puzzle::get_index_of(&flat, 0);
-        let end = vec![0; flat.len()];
+        let key = zobrist::hash(&flat);
         Puzzle {
             n: msize,
             flat,
-            end,
+            end: Arc::default(),
             blank,
             was: Move::Hold,
+            key,
         }
     }
 
@@ -86,9 +97,24 @@ impl Puzzle {
         new
     }
 
+    // Every board cloned from this one afterwards shares the same goal
+    //
     pub fn set_goal(&mut self, goal: &Puzzle) {
-        for i in 0..goal.flat.len() {
-            self.end[goal.flat[i] as usize] = i;
+        self.end = Arc::new(Goal::new(goal));
+    }
+
+    pub fn set_goal_spec(&mut self, spec: &GoalSpec) {
+        self.end = Arc::new(Goal::from_spec(spec));
+    }
+
+    // Whether the search can stop on this board: `end` itself when the goal
+    // is one whole board, any board matching the goal spec otherwise
+    //
+    pub fn is_goal(&self, end: &Puzzle) -> bool {
+        if self.end.is_exact() {
+            self == end
+        } else {
+            self.end.matches(&self.flat)
         }
     }
 
@@ -117,7 +143,7 @@ impl fmt::Debug for Puzzle {
 
 impl PartialEq for Puzzle {
     fn eq(&self, other: &Self) -> bool {
-        // ignore end
+        // ignore end and was, key follows from flat
         self.n == other.n
             && self.flat == other.flat
             && self.blank == other.blank
@@ -125,6 +151,14 @@ impl PartialEq for Puzzle {
 }
 impl Eq for Puzzle {}
 
+// Only what equality looks at, through the zobrist key
+//
+impl Hash for Puzzle {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.key);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -140,8 +174,9 @@ mod tests {
                 n: 3,
                 flat: vec![1, 0, 3, 1, 2, 3, 1, 2, 3],
                 blank: 1,
-                end: vec![],
-                was: Move::Hold
+                end: Arc::default(),
+                was: Move::Hold,
+                key: 0,
             }
         );
 
@@ -154,8 +189,9 @@ mod tests {
                 n: 3,
                 flat: vec![1, 1, 1, 2, 2, 2, 3, 3, 0],
                 blank: 8,
-                end: vec![],
-                was: Move::Hold
+                end: Arc::default(),
+                was: Move::Hold,
+                key: 0,
             }
         );
 
@@ -173,8 +209,9 @@ mod tests {
                 n: 4,
                 flat: vec![0, 4, 4, 4, 3, 3, 3, 3, 2, 2, 2, 2, 1, 1, 1, 1],
                 blank: 0,
-                end: vec![],
-                was: Move::Hold
+                end: Arc::default(),
+                was: Move::Hold,
+                key: 0,
             }
         );
     }
@@ -187,8 +224,9 @@ mod tests {
                 n: 3,
                 flat: vec![1, 2, 3, 4, 5, 6, 7, 8, 0],
                 blank: 8,
-                end: vec![],
-                was: Move::Hold
+                end: Arc::default(),
+                was: Move::Hold,
+                key: 0,
             }
         );
 
@@ -200,8 +238,9 @@ mod tests {
                     1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 0
                 ],
                 blank: 15,
-                end: vec![],
-                was: Move::Hold
+                end: Arc::default(),
+                was: Move::Hold,
+                key: 0,
             }
         );
 
@@ -214,8 +253,9 @@ mod tests {
                     17, 18, 19, 20, 21, 22, 23, 24, 0
                 ],
                 blank: 24,
-                end: vec![],
-                was: Move::Hold
+                end: Arc::default(),
+                was: Move::Hold,
+                key: 0,
             }
         );
     }
@@ -234,29 +274,33 @@ mod tests {
                     n: 3,
                     flat: vec![1, 0, 3, 4, 2, 5, 6, 7, 8],
                     blank: 1,
-                    end: vec![],
-                    was: Move::Hold
+                    end: Arc::default(),
+                    was: Move::Hold,
+                    key: 0,
                 },
                 Puzzle {
                     n: 3,
                     flat: vec![1, 2, 3, 4, 7, 5, 6, 0, 8],
                     blank: 7,
-                    end: vec![],
-                    was: Move::Hold
+                    end: Arc::default(),
+                    was: Move::Hold,
+                    key: 0,
                 },
                 Puzzle {
                     n: 3,
                     flat: vec![1, 2, 3, 0, 4, 5, 6, 7, 8],
                     blank: 3,
-                    end: vec![],
-                    was: Move::Hold
+                    end: Arc::default(),
+                    was: Move::Hold,
+                    key: 0,
                 },
                 Puzzle {
                     n: 3,
                     flat: vec![1, 2, 3, 4, 5, 0, 6, 7, 8],
                     blank: 5,
-                    end: vec![],
-                    was: Move::Hold
+                    end: Arc::default(),
+                    was: Move::Hold,
+                    key: 0,
                 },
             ]
         );
@@ -276,15 +320,17 @@ mod tests {
                     n: 3,
                     flat: vec![3, 1, 2, 0, 4, 5, 6, 7, 8],
                     blank: 3,
-                    end: vec![],
-                    was: Move::Hold
+                    end: Arc::default(),
+                    was: Move::Hold,
+                    key: 0,
                 },
                 Puzzle {
                     n: 3,
                     flat: vec![1, 0, 2, 3, 4, 5, 6, 7, 8],
                     blank: 1,
-                    end: vec![],
-                    was: Move::Hold
+                    end: Arc::default(),
+                    was: Move::Hold,
+                    key: 0,
                 },
             ]
         );
@@ -304,22 +350,25 @@ mod tests {
                     n: 3,
                     flat: vec![1, 2, 3, 4, 0, 6, 7, 5, 8],
                     blank: 4,
-                    end: vec![],
-                    was: Move::Hold
+                    end: Arc::default(),
+                    was: Move::Hold,
+                    key: 0,
                 },
                 Puzzle {
                     n: 3,
                     flat: vec![1, 2, 3, 4, 5, 6, 0, 7, 8],
                     blank: 6,
-                    end: vec![],
-                    was: Move::Hold
+                    end: Arc::default(),
+                    was: Move::Hold,
+                    key: 0,
                 },
                 Puzzle {
                     n: 3,
                     flat: vec![1, 2, 3, 4, 5, 6, 7, 8, 0],
                     blank: 8,
-                    end: vec![],
-                    was: Move::Hold
+                    end: Arc::default(),
+                    was: Move::Hold,
+                    key: 0,
                 }
             ]
         );
@@ -347,8 +396,9 @@ mod tests {
                         12,
                     ],
                     blank: 11,
-                    end: vec![],
-                    was: Move::Hold
+                    end: Arc::default(),
+                    was: Move::Hold,
+                    key: 0,
                 },
                 Puzzle {
                     n: 4,
@@ -357,8 +407,9 @@ mod tests {
                         15,
                     ],
                     blank: 14,
-                    end: vec![],
-                    was: Move::Hold
+                    end: Arc::default(),
+                    was: Move::Hold,
+                    key: 0,
                 },
             ]
         );
@@ -0,0 +1,200 @@
+use super::puzzle::Puzzle;
+
+// Binary indexed tree over the cells, tells how many of the cells before
+// a given one are already taken in O(log n)
+//
+// [https://en.wikipedia.org/wiki/Fenwick_tree]
+//
+struct Fenwick {
+    tree: Vec<usize>,
+}
+
+impl Fenwick {
+    fn new(n: usize) -> Fenwick {
+        Fenwick {
+            tree: vec![0; n + 1],
+        }
+    }
+
+    fn add(&mut self, i: usize, value: isize) {
+        let mut i = i + 1;
+        while i < self.tree.len() {
+            self.tree[i] = self.tree[i].wrapping_add(value as usize);
+            i += i & i.wrapping_neg();
+        }
+    }
+
+    // how many marked cells are strictly before `i`
+    fn before(&self, i: usize) -> usize {
+        let mut sum = 0;
+        let mut i = i;
+        while i > 0 {
+            sum += self.tree[i];
+            i -= i & i.wrapping_neg();
+        }
+        sum
+    }
+
+    // index of the `k`th marked cell, starting from 0
+    fn nth(&self, k: usize) -> usize {
+        let mut pos = 0;
+        let mut remaining = k + 1;
+        let mut step = (self.tree.len() - 1).next_power_of_two();
+        while step > 0 {
+            let next = pos + step;
+            if next < self.tree.len() && self.tree[next] < remaining {
+                pos = next;
+                remaining -= self.tree[next];
+            }
+            step >>= 1;
+        }
+        pos
+    }
+}
+
+// Number of ways to place `k` distinct items in `n` cells, n!/(n-k)!
+//
+pub fn permutations(n: usize, k: usize) -> u64 {
+    (n - k + 1..=n).map(|i| i as u64).product()
+}
+
+// [https://en.wikipedia.org/wiki/Lehmer_code]
+//
+// Each item is numbered by how many free cells come before it, like
+// merge_count_inversion counts the smaller elements on one side, and the
+// digits are read in the mixed radix n, n-1, ..., n-k+1. The result is
+// dense in [0, n!/(n-k)!) and keeps the lexicographic order.
+//
+pub fn rank_partial(items: &[usize], n: usize) -> u64 {
+    let mut taken = Fenwick::new(n);
+    let mut rank = 0;
+    for (i, &item) in items.iter().enumerate() {
+        let digit = item - taken.before(item);
+        taken.add(item, 1);
+        rank = rank * (n - i) as u64 + digit as u64;
+    }
+    rank
+}
+
+pub fn unrank_partial(mut rank: u64, k: usize, n: usize) -> Vec<usize> {
+    let mut digits = vec![0; k];
+    for i in (0..k).rev() {
+        let radix = (n - i) as u64;
+        digits[i] = (rank % radix) as usize;
+        rank /= radix;
+    }
+
+    let mut free = Fenwick::new(n);
+    for i in 0..n {
+        free.add(i, 1);
+    }
+    digits
+        .into_iter()
+        .map(|digit| {
+            let item = free.nth(digit);
+            free.add(item, -1);
+            item
+        })
+        .collect()
+}
+
+// A full permutation is a partial one where every item is placed, the
+// rank must fit in a u64 which holds up to 20! (4x4 boards)
+//
+pub fn rank(perm: &[u16]) -> u64 {
+    let items = perm.iter().map(|&t| t as usize).collect::<Vec<_>>();
+    rank_partial(&items, perm.len())
+}
+
+pub fn unrank(rank: u64, n: usize) -> Vec<u16> {
+    unrank_partial(rank, n, n)
+        .into_iter()
+        .map(|t| t as u16)
+        .collect()
+}
+
+pub fn rank_puzzle(puzzle: &Puzzle) -> u64 {
+    rank(&puzzle.flat)
+}
+
+pub fn unrank_puzzle(rank: u64, n: usize) -> Puzzle {
+    Puzzle::from_flat(n, unrank(rank, n * n))
+}
+
+// Ranks where the tiles of a pattern are, whatever the other tiles are,
+// in [0, (n*n)!/(n*n-k)!) for k tiles
+//
+pub fn rank_pattern(puzzle: &Puzzle, tiles: &[u16]) -> u64 {
+    let mut cells = vec![0; puzzle.flat.len()];
+    for (i, &t) in puzzle.flat.iter().enumerate() {
+        cells[t as usize] = i;
+    }
+    let items =
+        tiles.iter().map(|&t| cells[t as usize]).collect::<Vec<_>>();
+    rank_partial(&items, puzzle.flat.len())
+}
+
+// The cells the tiles of a pattern are in, in the same order as the tiles
+//
+pub fn unrank_pattern(rank: u64, tiles: usize, n: usize) -> Vec<usize> {
+    unrank_partial(rank, tiles, n * n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rank_known() {
+        assert_eq!(rank(&[0, 1, 2, 3]), 0);
+        assert_eq!(rank(&[0, 1, 3, 2]), 1);
+        assert_eq!(rank(&[1, 0, 2, 3]), 6);
+        assert_eq!(rank(&[3, 2, 1, 0]), 23);
+        assert_eq!(
+            rank(&[15, 14, 13, 12, 11, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0]),
+            permutations(16, 16) - 1
+        );
+    }
+
+    #[test]
+    fn test_rank_roundtrip() {
+        for r in 0..permutations(6, 6) {
+            let perm = unrank(r, 6);
+            assert_eq!(rank(&perm), r);
+        }
+        let p = Puzzle::new(crate::puzzle::PuzzleKind::Snail, 4);
+        assert_eq!(unrank_puzzle(rank_puzzle(&p), 4), p);
+    }
+
+    #[test]
+    fn test_partial_dense() {
+        let count = permutations(9, 3);
+        let mut seen = std::collections::HashSet::new();
+        for r in 0..count {
+            let items = unrank_partial(r, 3, 9);
+            assert_eq!(rank_partial(&items, 9), r);
+            seen.insert(items);
+        }
+        assert_eq!(count, 504);
+        assert_eq!(seen.len(), 504);
+    }
+
+    #[test]
+    fn test_rank_pattern() {
+        let p = Puzzle::from_matrix(
+            3,
+            vec![vec![1, 2, 3], vec![4, 5, 6], vec![7, 8, 0]],
+        );
+        let q = Puzzle::from_matrix(
+            3,
+            vec![vec![1, 2, 3], vec![4, 5, 6], vec![8, 0, 7]],
+        );
+
+        assert_eq!(rank_pattern(&p, &[1, 2]), rank_pattern(&q, &[1, 2]));
+        assert_ne!(rank_pattern(&p, &[7, 8]), rank_pattern(&q, &[7, 8]));
+        assert_eq!(
+            unrank_pattern(rank_pattern(&q, &[0, 7, 8]), 3, 3),
+            vec![7, 8, 6]
+        );
+    }
+}
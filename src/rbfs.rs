@@ -0,0 +1,226 @@
+use super::heuristics::Heuristic;
+use super::progress::{Progress, Status};
+use super::puzzle::Puzzle;
+use super::solution::Solution;
+use super::solver::{Limits, Outcome, Solver, Stats};
+
+const INFINITY: u32 = u32::MAX;
+
+enum SearchResult {
+    Found,
+    Backed(u32),
+    Interrupted,
+}
+
+// [https://en.wikipedia.org/wiki/Recursive_best-first_search]
+// [Korf, R. E. (1993). Linear-space best-first search.]
+//
+// Only the current path and its siblings are kept in memory, the f-value
+// of a forgotten subtree is backed up into its root so it can be
+// regenerated later without redoing the whole iteration like IDA*.
+//
+pub fn rbfs(
+    start: Puzzle,
+    end: &Puzzle,
+    h: &mut dyn Heuristic,
+    limits: &Limits,
+    progress: &mut dyn Progress,
+) -> Outcome {
+    let f = h.first_time(&start, end);
+    let mut path = vec![start];
+    let mut search = Search {
+        end,
+        h,
+        limits,
+        progress,
+        nodes: 0,
+        alive: 1,
+        max_states: 1,
+        best_f: f,
+    };
+
+    let result = search.search(&mut path, 0, f, f, INFINITY);
+    let stats = Stats {
+        total_opened: search.nodes,
+        max_states: search.max_states,
+    };
+    match result {
+        SearchResult::Found => {
+            Outcome::Solved(Solution::from_path(stats, path))
+        }
+        SearchResult::Backed(_) => Outcome::Unsolvable(stats),
+        SearchResult::Interrupted => Outcome::Interrupted(stats),
+    }
+}
+
+struct Child {
+    puzzle: Puzzle,
+    f: u32,
+    stored: u32,
+}
+
+struct Search<'a> {
+    end: &'a Puzzle,
+    h: &'a mut dyn Heuristic,
+    limits: &'a Limits,
+    progress: &'a mut dyn Progress,
+    nodes: usize,
+    alive: usize,
+    max_states: usize,
+    best_f: u32,
+}
+
+impl Search<'_> {
+    // `f` is the static value of the node, `stored` the one inherited
+    // from previous visits of its subtree
+    //
+    fn search(
+        &mut self,
+        path: &mut Vec<Puzzle>,
+        g: u32,
+        f: u32,
+        stored: u32,
+        bound: u32,
+    ) -> SearchResult {
+        self.nodes += 1;
+        if self.limits.exceeded(self.nodes) {
+            return SearchResult::Interrupted;
+        }
+        let current = path.last().unwrap();
+        if current == self.end {
+            return SearchResult::Found;
+        }
+
+        // f is g plus the heuristic of the current board
+        let h = f - g;
+        let mut children = current
+            .neighbors()
+            .into_iter()
+            .filter(|p| !path.contains(p))
+            .map(|p| {
+                let child_f = g + 1 + self.h.update(h, &p, self.end);
+                let child_stored = if f < stored {
+                    child_f.max(stored)
+                } else {
+                    child_f
+                };
+                Child {
+                    puzzle: p,
+                    f: child_f,
+                    stored: child_stored,
+                }
+            })
+            .collect::<Vec<_>>();
+        self.alive += children.len();
+        self.max_states = self.max_states.max(self.alive);
+
+        let result = loop {
+            children.sort_by_key(|c| c.stored);
+            let best = children.first().map_or(INFINITY, |c| c.stored);
+            if best > bound || best == INFINITY {
+                break SearchResult::Backed(best);
+            }
+            self.report(best);
+            let alternative =
+                children.get(1).map_or(INFINITY, |c| c.stored);
+
+            path.push(children[0].puzzle.clone());
+            let result = self.search(
+                path,
+                g + 1,
+                children[0].f,
+                best,
+                bound.min(alternative),
+            );
+            match result {
+                SearchResult::Backed(backed) => {
+                    children[0].stored = backed
+                }
+                _ => break result,
+            }
+            path.pop();
+        };
+        self.alive -= children.len();
+        result
+    }
+
+    fn report(&mut self, f: u32) {
+        if f > self.best_f {
+            self.best_f = f;
+            self.progress.report(&Status::Iteration {
+                bound: f,
+                nodes: self.nodes,
+            });
+        }
+    }
+}
+
+pub struct RBFS;
+
+impl Solver for RBFS {
+    fn name(&self) -> &'static str {
+        "RBFS"
+    }
+
+    fn solve(
+        &mut self,
+        start: Puzzle,
+        end: &Puzzle,
+        h: &mut dyn Heuristic,
+        limits: &Limits,
+        progress: &mut dyn Progress,
+    ) -> Outcome {
+        rbfs(start, end, h, limits, progress)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::heuristics::{get_heuristic, HeuristicFunc};
+    use crate::ida_star::ida_star;
+    use crate::progress::Silent;
+
+    fn solve_both(matrix: Vec<Vec<u16>>) -> (usize, usize) {
+        let goal = Puzzle::from_matrix(
+            3,
+            vec![vec![1, 2, 3], vec![4, 5, 6], vec![7, 8, 0]],
+        );
+        let mut start = Puzzle::from_matrix(3, matrix);
+        start.set_goal(&goal);
+
+        let mut h = get_heuristic(HeuristicFunc::ManhattanDistance);
+        let limits = Limits::default();
+        let optimal = ida_star(
+            start.clone(),
+            &goal,
+            h.as_mut(),
+            None,
+            &limits,
+            &mut Silent,
+        )
+        .solution()
+        .unwrap();
+        let found = rbfs(start, &goal, h.as_mut(), &limits, &mut Silent)
+            .solution()
+            .unwrap();
+
+        assert_eq!(found.end(), goal);
+        (found.moves.len(), optimal.moves.len())
+    }
+
+    #[test]
+    fn test_rbfs_optimal() {
+        let (found, optimal) =
+            solve_both(vec![vec![1, 2, 3], vec![4, 5, 6], vec![7, 0, 8]]);
+        assert_eq!(found, optimal);
+
+        let (found, optimal) =
+            solve_both(vec![vec![8, 1, 3], vec![4, 0, 2], vec![7, 6, 5]]);
+        assert_eq!(found, optimal);
+
+        let (found, optimal) =
+            solve_both(vec![vec![0, 1, 3], vec![4, 2, 5], vec![7, 8, 6]]);
+        assert_eq!(found, optimal);
+    }
+}
@@ -0,0 +1,314 @@
+use super::bucket::{BucketQueue, TieBreak};
+use super::heuristics::Heuristic;
+use super::moves::Move;
+use super::progress::{Progress, Status};
+use super::puzzle::Puzzle;
+use super::solution::Solution;
+use super::solver::{Limits, Outcome, Solver, Stats};
+use super::state::State;
+use super::zobrist::{ZobristMap, ZobristSet};
+
+pub const DEFAULT_LOOKAHEAD: usize = 4;
+pub const DEFAULT_TRIALS: usize = 1000;
+
+// How the agent looks ahead and what it learns before every move
+//
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rule {
+    // [Korf, R. E. (1990). Real-time heuristic search.]
+    // depth-first lookahead `lookahead` moves deep, the board gets the
+    // best value found under it
+    Lrta,
+    // [Koenig, S., Likhachev, M. (2006). Real-time adaptive A*.]
+    // A* for `lookahead` expansions, every expanded board gets the f of
+    // the best open one minus its own g
+    Rtaa,
+}
+
+// Heuristic values learned by the agent, kept from one trial to the next
+//
+struct Learned<'a> {
+    h: &'a mut dyn Heuristic,
+    table: ZobristMap<State, u32>,
+    updates: usize,
+}
+
+impl Learned<'_> {
+    fn get(&mut self, puzzle: &Puzzle, end: &Puzzle) -> u32 {
+        match self.table.get(&State::from(puzzle)) {
+            Some(&h) => h,
+            None => self.h.first_time(puzzle, end),
+        }
+    }
+
+    // Values only ever go up, they stay admissible
+    //
+    fn raise(&mut self, puzzle: &Puzzle, end: &Puzzle, h: u32) {
+        if h > self.get(puzzle, end) {
+            self.table.insert(State::from(puzzle), h);
+            self.updates += 1;
+        }
+    }
+}
+
+struct Agent<'a> {
+    end: &'a Puzzle,
+    learned: Learned<'a>,
+    rule: Rule,
+    lookahead: usize,
+    opened: usize,
+}
+
+impl Agent<'_> {
+    // Best value reachable from `puzzle` in `depth` moves, never below
+    // what was learned for the board itself: both are lower bounds
+    //
+    fn lookahead(&mut self, puzzle: &Puzzle, depth: usize) -> u32 {
+        self.opened += 1;
+        let h = self.learned.get(puzzle, self.end);
+        if depth == 0 || puzzle == self.end {
+            return h;
+        }
+        puzzle
+            .neighbors()
+            .iter()
+            .map(|neighbor| 1 + self.lookahead(neighbor, depth - 1))
+            .min()
+            .map_or(h, |best| best.max(h))
+    }
+
+    fn lrta_step(&mut self, current: &Puzzle) -> Option<Vec<Move>> {
+        let mut here = current.clone();
+        here.was = Move::Hold;
+        let depth = self.lookahead.max(1);
+        let (value, m) = here
+            .neighbors()
+            .iter()
+            .map(|n| (1 + self.lookahead(n, depth - 1), n.was.opposite()))
+            .min_by_key(|&(value, _)| value)?;
+        self.learned.raise(&here, self.end, value);
+        Some(vec![m])
+    }
+
+    fn rtaa_step(&mut self, current: &Puzzle) -> Option<Vec<Move>> {
+        let end = self.end;
+        let mut here = current.clone();
+        here.was = Move::Hold;
+        let mut open = BucketQueue::new(TieBreak::HighG);
+        let mut parents: ZobristMap<State, (u32, Option<(State, Move)>)> =
+            ZobristMap::default();
+        let mut closed = ZobristSet::default();
+        let mut expanded = vec![];
+        let h = self.learned.get(&here, end);
+        parents.insert(State::from(&here), (0, None));
+        open.push(h, 0, (here, 0));
+
+        let (best_f, best) = loop {
+            let (f, (puzzle, g)) = open.pop()?;
+            let state = State::from(&puzzle);
+            if closed.contains(&state) || parents[&state].0 < g {
+                continue;
+            }
+            if puzzle == *end || expanded.len() >= self.lookahead.max(1) {
+                break (f, state);
+            }
+            self.opened += 1;
+            for neighbor in puzzle.neighbors() {
+                let child = State::from(&neighbor);
+                let g = g + 1;
+                if parents.get(&child).is_some_and(|&(best, _)| best <= g)
+                {
+                    continue;
+                }
+                let m = neighbor.was.opposite();
+                parents.insert(child, (g, Some((state.clone(), m))));
+                let f = g + self.learned.get(&neighbor, end);
+                open.push(f, g, (neighbor, g));
+            }
+            closed.insert(state);
+            expanded.push((puzzle, g));
+        };
+
+        for (puzzle, g) in &expanded {
+            self.learned.raise(puzzle, end, best_f - g);
+        }
+        let mut moves = vec![];
+        let mut state = best;
+        while let Some((_, Some((parent, m)))) = parents.get(&state) {
+            moves.push(*m);
+            state = parent.clone();
+        }
+        moves.reverse();
+        Some(moves)
+    }
+
+    // One run from the start to the goal, learning on the way. The
+    // opened count covers this trial and all the ones before it
+    //
+    fn trial(&mut self, start: &Puzzle, limits: &Limits) -> Outcome {
+        let mut current = start.clone();
+        let mut moves = vec![];
+        while current != *self.end {
+            let stats = Stats {
+                total_opened: self.opened,
+                max_states: self.learned.table.len(),
+            };
+            if limits.exceeded(self.opened) {
+                return Outcome::Interrupted(stats);
+            }
+            let step = match self.rule {
+                Rule::Lrta => self.lrta_step(&current),
+                Rule::Rtaa => self.rtaa_step(&current),
+            };
+            let step = match step {
+                Some(step) => step,
+                None => return Outcome::Unsolvable(stats),
+            };
+            for m in &step {
+                m.apply(&mut current);
+            }
+            moves.extend(step);
+        }
+        Outcome::Solved(Solution {
+            stats: Stats {
+                total_opened: self.opened,
+                max_states: self.learned.table.len(),
+            },
+            start: start.clone(),
+            moves,
+        })
+    }
+}
+
+// Runs the agent from the start again and again with what it learned so
+// far. Trials can get longer before they get shorter, the values only
+// settle once a run learns nothing: the next ones would be the same, so
+// it stops there, or after `trials` runs.
+//
+#[allow(clippy::too_many_arguments)]
+pub fn trials(
+    start: Puzzle,
+    end: &Puzzle,
+    h: &mut dyn Heuristic,
+    rule: Rule,
+    lookahead: usize,
+    trials: usize,
+    limits: &Limits,
+    progress: &mut dyn Progress,
+) -> Vec<Outcome> {
+    let mut agent = Agent {
+        end,
+        learned: Learned {
+            h,
+            table: ZobristMap::default(),
+            updates: 0,
+        },
+        rule,
+        lookahead,
+        opened: 0,
+    };
+    let mut outcomes = vec![];
+    for trial in 0..trials.max(1) {
+        let updates = agent.learned.updates;
+        let opened = agent.opened;
+        let outcome = agent.trial(&start, limits);
+        let moves = match &outcome {
+            Outcome::Solved(s) => s.moves.len(),
+            _ => 0,
+        };
+        progress.report(&Status::Trial {
+            trial: trial + 1,
+            moves,
+            nodes: agent.opened - opened,
+        });
+        let solved = matches!(outcome, Outcome::Solved(_));
+        outcomes.push(outcome);
+        if !solved || agent.learned.updates == updates {
+            break;
+        }
+    }
+    outcomes
+}
+
+pub struct RealTime {
+    pub rule: Rule,
+    pub lookahead: usize,
+    pub trials: usize,
+}
+
+impl Solver for RealTime {
+    fn name(&self) -> &'static str {
+        match self.rule {
+            Rule::Lrta => "LRTA*",
+            Rule::Rtaa => "RTAA*",
+        }
+    }
+
+    fn solve(
+        &mut self,
+        start: Puzzle,
+        end: &Puzzle,
+        h: &mut dyn Heuristic,
+        limits: &Limits,
+        progress: &mut dyn Progress,
+    ) -> Outcome {
+        trials(
+            start,
+            end,
+            h,
+            self.rule,
+            self.lookahead,
+            self.trials,
+            limits,
+            progress,
+        )
+        .pop()
+        .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::a_star::a_star;
+    use crate::fixtures::subject_1;
+    use crate::heuristics::{get_heuristic, HeuristicFunc};
+    use crate::progress::Silent;
+
+    #[test]
+    fn test_trials_converge() {
+        let (start, goal) = subject_1();
+        let mut h = get_heuristic(HeuristicFunc::ManhattanDistance);
+        let optimal = a_star(
+            start.clone(),
+            &goal,
+            h.as_mut(),
+            &Limits::default(),
+            &mut Silent,
+        )
+        .solution()
+        .unwrap()
+        .moves
+        .len();
+
+        for (rule, lookahead) in [(Rule::Lrta, 2), (Rule::Rtaa, 16)] {
+            let outcomes = trials(
+                start.clone(),
+                &goal,
+                h.as_mut(),
+                rule,
+                lookahead,
+                1000,
+                &Limits::default(),
+                &mut Silent,
+            );
+            let lengths = outcomes
+                .into_iter()
+                .map(|o| o.solution().unwrap().moves.len())
+                .collect::<Vec<_>>();
+            assert!(lengths.len() < 1000, "{:?}", rule);
+            // the last trial learned nothing, its path is an optimal one
+            assert_eq!(*lengths.last().unwrap(), optimal, "{:?}", rule);
+        }
+    }
+}
@@ -0,0 +1,406 @@
+use super::heuristics::Heuristic;
+use super::moves::Move;
+use super::progress::{Progress, Status, Ticker};
+use super::puzzle::Puzzle;
+use super::solution::Solution;
+use super::solver::{Limits, Outcome, Solver, Stats};
+use super::state::{Context, State};
+use super::zobrist::ZobristMap;
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
+
+const INFINITY: u32 = u32::MAX;
+
+pub const DEFAULT_MEMORY: usize = 1_000_000;
+
+// Lowest f first, deepest first among equals: the first key of a set is
+// the next node to expand and the last one the first leaf to forget
+//
+type Key = (u32, Reverse<u32>, usize);
+
+struct Node {
+    state: State,
+    // the move from the parent, Hold for the root
+    m: Move,
+    g: u32,
+    f: u32,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    // successors dropped from memory and the f-value they had
+    forgotten: Vec<(State, u32)>,
+    expanded: bool,
+}
+
+impl Node {
+    fn key(&self, id: usize) -> Key {
+        (self.f, Reverse(self.g), id)
+    }
+}
+
+// [Russell, S. (1992). Efficient memory-bounded search methods.]
+// [https://en.wikipedia.org/wiki/SMA*]
+//
+// Nodes are expanded all at once like in A*. When memory is full the
+// worst leaf is dropped and its f-value is kept by its parent, which is
+// put back in the open list so the subtree can be regenerated later. A
+// board already in memory at the same depth or less is not generated
+// again.
+//
+pub fn sma_star(
+    start: Puzzle,
+    end: &Puzzle,
+    h: &mut dyn Heuristic,
+    memory: usize,
+    limits: &Limits,
+    progress: &mut dyn Progress,
+) -> Outcome {
+    let mut search = Search {
+        nodes: vec![],
+        free: vec![],
+        index: ZobristMap::default(),
+        open: BTreeSet::new(),
+        leaves: BTreeSet::new(),
+        goal: State::from(end),
+        memory: memory.max(2),
+        alive: 0,
+        cut_off: false,
+        stats: Stats::default(),
+    };
+    let mut ticker = Ticker::new();
+    let mut context = Context::new(&start);
+
+    let f = h.first_time(&start, end);
+    search.insert(Node {
+        state: State::from(&start),
+        m: Move::Hold,
+        g: 0,
+        f,
+        parent: None,
+        children: vec![],
+        forgotten: vec![],
+        expanded: false,
+    });
+
+    while let Some(&(f, _, id)) = search.open.iter().next() {
+        if f == INFINITY {
+            break;
+        }
+        if search.node(id).state == search.goal {
+            return Outcome::Solved(Solution {
+                stats: search.stats,
+                start,
+                moves: search.moves(id),
+            });
+        }
+        if limits.exceeded(search.stats.total_opened) {
+            return Outcome::Interrupted(search.stats);
+        }
+        if ticker.tick() {
+            progress.report(&Status::Frontier {
+                open: search.open.len(),
+                closed: search.alive - search.open.len(),
+                min_f: f,
+                rate: ticker.rate(),
+            });
+        }
+        search.expand(id, &mut context, end, h);
+    }
+
+    // the paths left need more memory than allowed, the board may still
+    // have a solution
+    if search.cut_off {
+        return Outcome::Interrupted(search.stats);
+    }
+    Outcome::Unsolvable(search.stats)
+}
+
+struct Search {
+    nodes: Vec<Option<Node>>,
+    free: Vec<usize>,
+    // the node of every board in memory, the shallowest one when there
+    // are several
+    index: ZobristMap<State, usize>,
+    open: BTreeSet<Key>,
+    leaves: BTreeSet<Key>,
+    goal: State,
+    memory: usize,
+    alive: usize,
+    // whether a successor was dropped for a path longer than the memory
+    cut_off: bool,
+    stats: Stats,
+}
+
+impl Search {
+    fn node(&self, id: usize) -> &Node {
+        self.nodes[id].as_ref().unwrap()
+    }
+
+    fn node_mut(&mut self, id: usize) -> &mut Node {
+        self.nodes[id].as_mut().unwrap()
+    }
+
+    fn insert(&mut self, node: Node) -> usize {
+        let id = match self.free.pop() {
+            Some(id) => id,
+            None => {
+                self.nodes.push(None);
+                self.nodes.len() - 1
+            }
+        };
+        self.open.insert(node.key(id));
+        self.leaves.insert(node.key(id));
+        self.index.insert(node.state.clone(), id);
+        self.nodes[id] = Some(node);
+
+        self.alive += 1;
+        self.stats.total_opened += 1;
+        self.stats.max_states = self.stats.max_states.max(self.alive);
+        id
+    }
+
+    fn moves(&self, mut id: usize) -> Vec<Move> {
+        let mut moves = vec![];
+        while let Some(parent) = self.node(id).parent {
+            moves.push(self.node(id).m);
+            id = parent;
+        }
+        moves.reverse();
+        moves
+    }
+
+    // Generates every successor of the node that is not in memory, which
+    // is all of them the first time and the forgotten ones afterwards. A
+    // forgotten successor gets back the f-value it had. Successors that
+    // do not fit stay forgotten, the node stays open to generate them
+    // later.
+    //
+    fn expand(
+        &mut self,
+        id: usize,
+        context: &mut Context,
+        end: &Puzzle,
+        h: &mut dyn Heuristic,
+    ) {
+        let node = self.node(id);
+        let (g, f) = (node.g, node.f);
+        let mut successors = vec![];
+        context.neighbors(&node.state, |m, state, puzzle| {
+            successors.push((m, state, h.first_time(puzzle, end)));
+        });
+
+        let key = self.node(id).key(id);
+        self.open.remove(&key);
+        let mut forgotten =
+            std::mem::take(&mut self.node_mut(id).forgotten);
+        let depth = g + 1;
+        let mut full = false;
+        for (m, state, child_h) in successors {
+            let before = forgotten
+                .iter()
+                .position(|(s, _)| *s == state)
+                .map(|i| forgotten.swap_remove(i).1);
+            if let Some(&other) = self.index.get(&state) {
+                if self.node(other).g <= depth {
+                    continue;
+                }
+                // a deeper copy goes when nothing hangs from it
+                if self.node(other).children.is_empty() {
+                    self.forget(other);
+                }
+            }
+            // a path this long fills the memory, only a goal is worth it
+            let length = depth as usize + 1;
+            let fits = length < self.memory
+                || length == self.memory && state == self.goal;
+            let child_f = if fits {
+                f.max(depth + child_h).max(before.unwrap_or(0))
+            } else {
+                self.cut_off = true;
+                INFINITY
+            };
+            full = full || !self.make_room(id);
+            if full {
+                forgotten.push((state, child_f));
+                continue;
+            }
+            let child = self.insert(Node {
+                state,
+                m,
+                g: depth,
+                f: child_f,
+                parent: Some(id),
+                children: vec![],
+                forgotten: vec![],
+                expanded: false,
+            });
+            let key = self.node(id).key(id);
+            self.leaves.remove(&key);
+            self.node_mut(id).children.push(child);
+        }
+        if !forgotten.is_empty() {
+            let key = self.node(id).key(id);
+            self.open.insert(key);
+        }
+        self.node_mut(id).forgotten.extend(forgotten);
+        self.node_mut(id).expanded = true;
+        self.backup(id);
+    }
+
+    // Drops leaves until a new node fits, the node being expanded and the
+    // root are never dropped
+    //
+    fn make_room(&mut self, expanding: usize) -> bool {
+        while self.alive >= self.memory {
+            let worst =
+                self.leaves.iter().rev().map(|&(_, _, id)| id).find(
+                    |&id| {
+                        id != expanding && self.node(id).parent.is_some()
+                    },
+                );
+            match worst {
+                Some(leaf) => self.forget(leaf),
+                None => return false,
+            }
+        }
+        true
+    }
+
+    fn forget(&mut self, leaf: usize) {
+        let node = self.nodes[leaf].take().unwrap();
+        self.open.remove(&node.key(leaf));
+        self.leaves.remove(&node.key(leaf));
+        if self.index.get(&node.state) == Some(&leaf) {
+            self.index.remove(&node.state);
+        }
+        self.free.push(leaf);
+        self.alive -= 1;
+
+        let parent = node.parent.unwrap();
+        let key = self.node(parent).key(parent);
+        let p = self.node_mut(parent);
+        p.children.retain(|&c| c != leaf);
+        p.forgotten.push((node.state, node.f));
+        let is_leaf = p.children.is_empty();
+        self.open.insert(key);
+        if is_leaf {
+            self.leaves.insert(key);
+        }
+    }
+
+    fn update(&mut self, id: usize, f: u32) {
+        let key = self.node(id).key(id);
+        let in_open = self.open.remove(&key);
+        let in_leaves = self.leaves.remove(&key);
+        self.node_mut(id).f = f;
+        let key = self.node(id).key(id);
+        if in_open {
+            self.open.insert(key);
+        }
+        if in_leaves {
+            self.leaves.insert(key);
+        }
+    }
+
+    // Once expanded, a node is only as good as its best successor, in
+    // memory or forgotten
+    //
+    fn backup(&mut self, mut id: usize) {
+        loop {
+            let node = self.node(id);
+            if !node.expanded {
+                return;
+            }
+            let best = node
+                .children
+                .iter()
+                .map(|&c| self.node(c).f)
+                .min()
+                .unwrap_or(INFINITY)
+                .min(
+                    node.forgotten
+                        .iter()
+                        .map(|&(_, f)| f)
+                        .min()
+                        .unwrap_or(INFINITY),
+                );
+            if best == node.f {
+                return;
+            }
+            let parent = node.parent;
+            self.update(id, best);
+            match parent {
+                Some(parent) => id = parent,
+                None => return,
+            }
+        }
+    }
+}
+
+pub struct SMAStar {
+    pub memory: usize,
+}
+
+impl Solver for SMAStar {
+    fn name(&self) -> &'static str {
+        "SMA*"
+    }
+
+    fn solve(
+        &mut self,
+        start: Puzzle,
+        end: &Puzzle,
+        h: &mut dyn Heuristic,
+        limits: &Limits,
+        progress: &mut dyn Progress,
+    ) -> Outcome {
+        sma_star(start, end, h, self.memory, limits, progress)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::heuristics::{get_heuristic, HeuristicFunc};
+    use crate::progress::Silent;
+
+    fn solve_with(memory: usize) -> Outcome {
+        let goal = Puzzle::from_matrix(
+            3,
+            vec![vec![1, 2, 3], vec![4, 5, 6], vec![7, 8, 0]],
+        );
+        let mut start = Puzzle::from_matrix(
+            3,
+            vec![vec![4, 1, 3], vec![7, 2, 6], vec![0, 5, 8]],
+        );
+        start.set_goal(&goal);
+
+        let mut h = get_heuristic(HeuristicFunc::ManhattanDistance);
+        let outcome = sma_star(
+            start,
+            &goal,
+            h.as_mut(),
+            memory,
+            &Limits::default(),
+            &mut Silent,
+        );
+        if let Outcome::Solved(solution) = &outcome {
+            assert!(solution.stats.max_states <= memory);
+            assert_eq!(solution.end(), goal);
+        }
+        outcome
+    }
+
+    fn moves(outcome: Outcome) -> Option<usize> {
+        outcome.solution().map(|s| s.moves.len())
+    }
+
+    #[test]
+    fn test_sma_star_optimal() {
+        assert_eq!(moves(solve_with(DEFAULT_MEMORY)), Some(6));
+        assert_eq!(moves(solve_with(50)), Some(6));
+        assert_eq!(moves(solve_with(20)), Some(6));
+        // just enough for the path, then too little
+        assert_eq!(moves(solve_with(7)), Some(6));
+        assert!(matches!(solve_with(6), Outcome::Interrupted(_)));
+    }
+}
@@ -1,50 +1,231 @@
-use super::a_star::a_star;
-use super::args::Sia;
-use super::heuristics;
-use super::ida_star::ida_star;
+use super::a_star::{AStar, Greedy, UniformCost, WeightedAStar};
+use super::beam::Beam;
+use super::bfs::BFS;
+use super::bucket::TieBreak;
+use super::constructive::Constructive;
+use super::hda_star::HDAStar;
+use super::heuristics::{self, HeuristicFunc};
+use super::hierarchical::Hierarchical;
+use super::ida_star::IDAStar;
+use super::moves::Move;
+use super::progress::Progress;
 use super::puzzle::Puzzle;
+use super::rbfs::RBFS;
+use super::real_time::{RealTime, Rule};
+use super::sma_star::SMAStar;
+use super::solver::{Limits, Outcome, Solver, Stats};
 
 #[derive(Clone, Copy, Debug)]
 pub enum Algorithm {
-    AStar,
-    IDAStar,
+    AStar {
+        tie_break: TieBreak,
+    },
+    IDAStar {
+        table_size: usize,
+    },
+    RBFS,
+    SMAStar {
+        memory: usize,
+    },
+    Greedy {
+        tie_break: TieBreak,
+    },
+    UniformCost {
+        tie_break: TieBreak,
+    },
+    BFS,
+    HDAStar {
+        threads: usize,
+    },
+    WeightedAStar {
+        weight: u32,
+        tie_break: TieBreak,
+    },
+    Constructive,
+    Hierarchical {
+        inner: usize,
+    },
+    Beam {
+        width: usize,
+        finish: bool,
+    },
+    RealTime {
+        rule: Rule,
+        lookahead: usize,
+        trials: usize,
+    },
 }
 
+impl Algorithm {
+    pub fn solver(self) -> Box<dyn Solver> {
+        match self {
+            Algorithm::AStar { tie_break } => {
+                Box::new(AStar { tie_break })
+            }
+            Algorithm::IDAStar { table_size } => Box::new(IDAStar {
+                table_size,
+                hits: 0,
+            }),
+            Algorithm::RBFS => Box::new(RBFS),
+            Algorithm::SMAStar { memory } => Box::new(SMAStar { memory }),
+            Algorithm::Greedy { tie_break } => {
+                Box::new(Greedy { tie_break })
+            }
+            Algorithm::UniformCost { tie_break } => {
+                Box::new(UniformCost { tie_break })
+            }
+            Algorithm::BFS => Box::new(BFS),
+            Algorithm::HDAStar { threads } => {
+                Box::new(HDAStar { threads })
+            }
+            Algorithm::WeightedAStar { weight, tie_break } => {
+                Box::new(WeightedAStar { weight, tie_break })
+            }
+            Algorithm::Constructive => Box::new(Constructive),
+            Algorithm::Hierarchical { inner } => Box::new(Hierarchical {
+                inner,
+                lower_bound: 0,
+            }),
+            Algorithm::Beam { width, finish } => Box::new(Beam {
+                width,
+                finish,
+                handed_over: 0,
+            }),
+            Algorithm::RealTime {
+                rule,
+                lookahead,
+                trials,
+            } => Box::new(RealTime {
+                rule,
+                lookahead,
+                trials,
+            }),
+        }
+    }
+
+    // Whether the outcome of a search with these stats is sure to be the
+    // shortest solution, or the proof there is none, with any of the
+    // heuristics since they never overestimate. SMA* only is when it never
+    // had to forget a node, it then searched like A*.
+    //
+    pub fn is_optimal(self, stats: &Stats) -> bool {
+        match self {
+            Algorithm::SMAStar { memory } => stats.max_states < memory,
+            _ => !matches!(
+                self,
+                Algorithm::Greedy { .. }
+                    | Algorithm::WeightedAStar { .. }
+                    | Algorithm::Constructive
+                    | Algorithm::Hierarchical { .. }
+                    | Algorithm::Beam { .. }
+                    | Algorithm::RealTime { .. }
+            ),
+        }
+    }
+
+    // Whether the search stops on any board of a goal spec rather than on
+    // the one end board it is given
+    //
+    pub fn searches_goal_spec(self) -> bool {
+        matches!(
+            self,
+            Algorithm::AStar { .. }
+                | Algorithm::Greedy { .. }
+                | Algorithm::UniformCost { .. }
+                | Algorithm::WeightedAStar { .. }
+                | Algorithm::IDAStar { .. }
+        )
+    }
+}
+
+// The boards of a solution are only rebuilt from its moves when they are
+// needed, long solutions stay small
+//
 pub struct Solution {
-    pub total_opened: usize,
-    pub max_states: usize,
-    pub path: Vec<Puzzle>,
+    pub stats: Stats,
+    pub start: Puzzle,
+    pub moves: Vec<Move>,
+}
+
+impl Solution {
+    pub fn from_path(stats: Stats, path: Vec<Puzzle>) -> Solution {
+        let moves = path[1..].iter().map(|p| p.was.opposite()).collect();
+        Solution {
+            stats,
+            start: path.into_iter().next().unwrap(),
+            moves,
+        }
+    }
+
+    pub fn boards(&self) -> impl Iterator<Item = Puzzle> + '_ {
+        let mut start = self.start.clone();
+        start.was = Move::Hold;
+        let rest = self.moves.iter().scan(start.clone(), |puzzle, m| {
+            m.apply(puzzle);
+            Some(puzzle.clone())
+        });
+        std::iter::once(start).chain(rest)
+    }
+
+    pub fn path(&self) -> Vec<Puzzle> {
+        self.boards().collect()
+    }
+
+    pub fn end(&self) -> Puzzle {
+        let mut end = self.start.clone();
+        for m in &self.moves {
+            m.apply(&mut end);
+        }
+        end
+    }
 }
 
 use std::fmt;
 
+// Past this many cells only the moves are printed, every board of a
+// large solution would take gigabytes
+//
+const MAX_PRINTED_CELLS: usize = 10_000;
+
 impl fmt::Display for Solution {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(f, "Solution:\n")?;
-        for puzzle in &self.path {
-            writeln!(f, "{:?}", puzzle.was.opposite())?;
-            for chunk in puzzle.flat.chunks(puzzle.n) {
-                writeln!(f, "{:?}", chunk)?;
+        if self.start.flat.len() * (self.moves.len() + 1)
+            > MAX_PRINTED_CELLS
+        {
+            let letters = self
+                .moves
+                .iter()
+                .map(|m| format!("{:?}", m).remove(0))
+                .collect::<String>();
+            writeln!(f, "{}\n", letters)?;
+        } else {
+            for puzzle in self.boards() {
+                writeln!(f, "{:?}", puzzle.was.opposite())?;
+                for chunk in puzzle.flat.chunks(puzzle.n) {
+                    writeln!(f, "{:?}", chunk)?;
+                }
+                writeln!(f, "")?;
             }
-            writeln!(f, "")?;
         }
-        writeln!(f, "Total opened: {}", self.total_opened)?;
-        writeln!(f, "Max states: {}", self.max_states)?;
-        writeln!(f, "Number of moves: {}", self.path.len() - 1)?;
+        writeln!(f, "Total opened: {}", self.stats.total_opened)?;
+        writeln!(f, "Max states: {}", self.stats.max_states)?;
+        writeln!(f, "Number of moves: {}", self.moves.len())?;
         Ok(())
     }
 }
 
-pub fn solve(start: Puzzle, end: Puzzle, options: &Sia) {
-    let mut h = heuristics::get_heuristic(options.heuristic);
-
-    let solution = match options.algorithm {
-        Algorithm::AStar => a_star(start, end, h.as_mut()),
-        Algorithm::IDAStar => ida_star(start, end, h.as_mut()),
-    };
+pub fn solve(
+    solver: &mut dyn Solver,
+    start: Puzzle,
+    end: &Puzzle,
+    heuristic: HeuristicFunc,
+    limits: &Limits,
+    progress: &mut dyn Progress,
+) -> Outcome {
+    let mut h = heuristics::get_heuristic(heuristic);
 
-    match solution {
-        Some(s) => print!("{}", s),
-        None => println!("No solution !"),
-    };
+    let outcome = solver.solve(start, end, h.as_mut(), limits, progress);
+    progress.finish();
+    outcome
 }
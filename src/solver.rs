@@ -0,0 +1,106 @@
+use super::heuristics::Heuristic;
+use super::progress::Progress;
+use super::puzzle::Puzzle;
+use super::solution::Solution;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+#[derive(Clone, Debug, Default)]
+pub struct Limits {
+    pub max_nodes: Option<usize>,
+    // set from another thread to stop the search early
+    pub cancel: Option<Arc<AtomicBool>>,
+}
+
+impl Limits {
+    pub fn exceeded(&self, opened: usize) -> bool {
+        let cancelled = match &self.cancel {
+            Some(cancel) => cancel.load(Ordering::Relaxed),
+            None => false,
+        };
+        cancelled
+            || match self.max_nodes {
+                Some(max) => opened > max,
+                None => false,
+            }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Stats {
+    pub total_opened: usize,
+    pub max_states: usize,
+}
+
+pub enum Outcome {
+    Solved(Solution),
+    Unsolvable(Stats),
+    Interrupted(Stats),
+}
+
+impl Outcome {
+    pub fn solution(self) -> Option<Solution> {
+        match self {
+            Outcome::Solved(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn stats(&self) -> Stats {
+        match self {
+            Outcome::Solved(s) => s.stats,
+            Outcome::Unsolvable(stats) | Outcome::Interrupted(stats) => {
+                *stats
+            }
+        }
+    }
+}
+
+// Every search strategy goes through this trait, so the CLI and
+// library users can run any of them, or their own, the same way
+//
+pub trait Solver {
+    fn name(&self) -> &'static str;
+
+    fn solve(
+        &mut self,
+        start: Puzzle,
+        end: &Puzzle,
+        h: &mut dyn Heuristic,
+        limits: &Limits,
+        progress: &mut dyn Progress,
+    ) -> Outcome;
+
+    // Figures only this solver measures, about its last solve, one line
+    // each for the summary
+    //
+    fn details(&self) -> Vec<String> {
+        vec![]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits_exceeded() {
+        let none = Limits::default();
+        let some = Limits {
+            max_nodes: Some(10),
+            cancel: None,
+        };
+        let cancel = Arc::new(AtomicBool::new(false));
+        let cancelled = Limits {
+            max_nodes: None,
+            cancel: Some(cancel.clone()),
+        };
+
+        assert!(!none.exceeded(usize::MAX));
+        assert!(!some.exceeded(10));
+        assert!(!cancelled.exceeded(usize::MAX));
+        cancel.store(true, Ordering::Relaxed);
+        assert!(cancelled.exceeded(0));
+        assert!(some.exceeded(11));
+    }
+}
@@ -0,0 +1,166 @@
+use super::moves::Move;
+use super::puzzle::Puzzle;
+use super::zobrist;
+use std::hash::{Hash, Hasher};
+
+const NIBBLE_CELLS: usize = 16;
+const BYTE_CELLS: usize = 256;
+
+// Compact copy of a board used as a search node: 4 bits per tile packed in
+// a u64 up to 4x4, one byte per tile up to 16x16 and two above that. The
+// bigger ones keep the zobrist key of the board to be hashed in O(1).
+//
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum State {
+    Packed(u64),
+    Bytes(u64, Box<[u8]>),
+}
+
+impl State {
+    pub fn pack(flat: &[u16]) -> State {
+        State::pack_with_key(flat, zobrist::hash(flat))
+    }
+
+    fn pack_with_key(flat: &[u16], key: u64) -> State {
+        if flat.len() <= NIBBLE_CELLS {
+            let packed = flat
+                .iter()
+                .enumerate()
+                .fold(0, |acc, (i, &t)| acc | (t as u64) << (i * 4));
+            State::Packed(packed)
+        } else if flat.len() <= BYTE_CELLS {
+            State::Bytes(key, flat.iter().map(|&t| t as u8).collect())
+        } else {
+            State::Bytes(
+                key,
+                flat.iter().flat_map(|t| t.to_le_bytes()).collect(),
+            )
+        }
+    }
+
+    pub fn tile(&self, cells: usize, i: usize) -> u16 {
+        match self {
+            State::Packed(packed) => (packed >> (i * 4) & 0xf) as u16,
+            State::Bytes(_, bytes) if cells <= BYTE_CELLS => {
+                bytes[i] as u16
+            }
+            State::Bytes(_, bytes) => {
+                u16::from_le_bytes([bytes[i * 2], bytes[i * 2 + 1]])
+            }
+        }
+    }
+
+    pub fn unpack(&self, flat: &mut [u16]) {
+        let cells = flat.len();
+        for (i, tile) in flat.iter_mut().enumerate() {
+            *tile = self.tile(cells, i);
+        }
+    }
+}
+
+impl Hash for State {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            State::Packed(packed) => {
+                state.write_u64(zobrist::mix(*packed))
+            }
+            State::Bytes(key, _) => state.write_u64(*key),
+        }
+    }
+}
+
+impl From<&Puzzle> for State {
+    fn from(puzzle: &Puzzle) -> State {
+        State::pack_with_key(&puzzle.flat, puzzle.key)
+    }
+}
+
+// What all the nodes of a search have in common: the size of the board and
+// the goal mapping are kept once here, along with a working board states
+// are unpacked into when they get expanded
+//
+pub struct Context {
+    puzzle: Puzzle,
+}
+
+impl Context {
+    pub fn new(start: &Puzzle) -> Context {
+        Context {
+            puzzle: start.clone(),
+        }
+    }
+
+    pub fn load(&mut self, state: &State) -> &Puzzle {
+        state.unpack(&mut self.puzzle.flat);
+        self.puzzle.key = match state {
+            State::Packed(_) => zobrist::hash(&self.puzzle.flat),
+            State::Bytes(key, _) => *key,
+        };
+        self.puzzle.blank = Puzzle::get_index_of(&self.puzzle.flat, 0);
+        self.puzzle.was = Move::Hold;
+        &self.puzzle
+    }
+
+    // Calls `f` with every state one move away and the move leading to it,
+    // the board it is given is the neighbor itself so heuristics can be
+    // computed on it
+    //
+    pub fn neighbors<F>(&mut self, state: &State, mut f: F)
+    where
+        F: FnMut(Move, State, &Puzzle),
+    {
+        self.load(state);
+        for m in Move::moves(&self.puzzle) {
+            m.apply(&mut self.puzzle);
+            f(m, State::from(&self.puzzle), &self.puzzle);
+            m.opposite().apply(&mut self.puzzle);
+        }
+        self.puzzle.was = Move::Hold;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pack_roundtrip() {
+        for n in [3, 4, 5, 17] {
+            let flat =
+                Puzzle::new(crate::puzzle::PuzzleKind::Snail, n).flat;
+            let state = State::pack(&flat);
+            let mut unpacked = vec![0; flat.len()];
+            state.unpack(&mut unpacked);
+
+            assert_eq!(unpacked, flat);
+            match state {
+                State::Packed(_) => assert!(n <= 4),
+                State::Bytes(key, bytes) => {
+                    assert_eq!(key, zobrist::hash(&flat));
+                    assert_eq!(
+                        bytes.len(),
+                        if n > 16 { 578 } else { n * n }
+                    )
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_context_neighbors() {
+        let p = Puzzle::from_matrix(
+            3,
+            vec![vec![1, 2, 3], vec![4, 0, 5], vec![6, 7, 8]],
+        );
+        let mut context = Context::new(&p);
+        let mut neighbors = vec![];
+        context.neighbors(&State::from(&p), |m, state, puzzle| {
+            assert_eq!(state, State::from(puzzle));
+            assert_eq!(puzzle.was, m.opposite());
+            neighbors.push(puzzle.clone());
+        });
+
+        assert_eq!(neighbors, p.neighbors());
+        assert_eq!(context.load(&State::from(&p)), &p);
+    }
+}
@@ -0,0 +1,248 @@
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use super::heuristics::Heuristic;
+use super::moves::Move;
+use super::progress::Progress;
+use super::puzzle::Puzzle;
+use super::rank;
+use super::solution::Solution;
+use super::solver::{Limits, Outcome, Solver, Stats};
+
+const MAGIC: &[u8; 4] = b"NPDT";
+const UNREACHABLE: u8 = 3;
+
+// Exact distance to the goal of every board of a small puzzle, indexed by
+// the rank of its permutation.
+//
+// Only the distance modulo 3 is stored, in 2 bits: the neighbors of a
+// board are one move closer or one move further, and the two cases have
+// different values modulo 3. Half of the permutations cannot reach the
+// goal so this is about 4 bits per reachable board, 90 KB for the 3x3.
+//
+pub struct DistanceTable {
+    pub n: usize,
+    pub goal: Vec<u16>,
+    cells: Vec<u8>,
+}
+
+impl DistanceTable {
+    // Breadth-first search from the goal over the whole state space, the
+    // returned histogram counts the boards at each distance
+    //
+    pub fn build(
+        goal: &Puzzle,
+    ) -> Result<(DistanceTable, Vec<usize>), Box<dyn Error>> {
+        if goal.n > 3 {
+            return Err(
+                "Distance tables only fit boards up to 3x3 !".into()
+            );
+        }
+        let size = rank::permutations(goal.flat.len(), goal.flat.len());
+        let mut table = DistanceTable {
+            n: goal.n,
+            goal: goal.flat.clone(),
+            cells: vec![0xff; (size as usize).div_ceil(4)],
+        };
+        let mut histogram = vec![];
+
+        let mut layer = vec![rank::rank(&goal.flat)];
+        table.set(layer[0], 0);
+        let mut depth = 0;
+        while !layer.is_empty() {
+            histogram.push(layer.len());
+            depth += 1;
+            let mut next = vec![];
+            for &r in &layer {
+                for neighbor in table.neighbors(r) {
+                    if table.get(neighbor) == UNREACHABLE {
+                        table.set(neighbor, depth % 3);
+                        next.push(neighbor);
+                    }
+                }
+            }
+            layer = next;
+        }
+        Ok((table, histogram))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(self.n as u8);
+        bytes.extend(self.goal.iter().map(|&t| t as u8));
+        bytes.extend(&self.cells);
+        fs::write(path, bytes)?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<DistanceTable, Box<dyn Error>> {
+        let bytes = fs::read(path)?;
+        let invalid = || "Invalid distance table file !".into();
+        if bytes.len() < 5 || &bytes[..4] != MAGIC {
+            return Err(invalid());
+        }
+        let n = bytes[4] as usize;
+        if !(2..=3).contains(&n) {
+            return Err(invalid());
+        }
+        let size = rank::permutations(n * n, n * n) as usize;
+        if bytes.len() != 5 + n * n + size.div_ceil(4) {
+            return Err(invalid());
+        }
+        Ok(DistanceTable {
+            n,
+            goal: bytes[5..5 + n * n].iter().map(|&t| t as u16).collect(),
+            cells: bytes[5 + n * n..].to_vec(),
+        })
+    }
+
+    fn get(&self, r: u64) -> u8 {
+        let r = r as usize;
+        self.cells[r / 4] >> (r % 4 * 2) & 0b11
+    }
+
+    fn set(&mut self, r: u64, value: u8) {
+        let r = r as usize;
+        let shift = r % 4 * 2;
+        self.cells[r / 4] &= !(0b11 << shift);
+        self.cells[r / 4] |= value << shift;
+    }
+
+    fn neighbors(&self, r: u64) -> Vec<u64> {
+        let mut flat = rank::unrank(r, self.n * self.n);
+        let blank = Puzzle::get_index_of(&flat, 0);
+        let (row, col) = (blank / self.n, blank % self.n);
+        let mut swaps = vec![];
+        if row > 0 {
+            swaps.push(blank - self.n);
+        }
+        if row + 1 < self.n {
+            swaps.push(blank + self.n);
+        }
+        if col > 0 {
+            swaps.push(blank - 1);
+        }
+        if col + 1 < self.n {
+            swaps.push(blank + 1);
+        }
+        swaps
+            .into_iter()
+            .map(|i| {
+                flat.swap(blank, i);
+                let neighbor = rank::rank(&flat);
+                flat.swap(blank, i);
+                neighbor
+            })
+            .collect()
+    }
+
+    pub fn is_reachable(&self, puzzle: &Puzzle) -> bool {
+        self.get(rank::rank_puzzle(puzzle)) != UNREACHABLE
+    }
+
+    // Walks down the table: there is always a neighbor one move closer and
+    // it is the only one whose distance is one less modulo 3
+    //
+    pub fn solve(&self, start: &Puzzle) -> Option<(Vec<Puzzle>, usize)> {
+        if start.n != self.n || !self.is_reachable(start) {
+            return None;
+        }
+        let mut lookups = 1;
+        let mut path = vec![start.clone()];
+        let mut current = start.clone();
+        current.was = Move::Hold;
+        while current.flat != self.goal {
+            let closer = (self.get(rank::rank_puzzle(&current)) + 2) % 3;
+            current = current
+                .neighbors()
+                .into_iter()
+                .find(|p| {
+                    lookups += 1;
+                    self.get(rank::rank_puzzle(p)) == closer
+                })
+                .unwrap();
+            path.push(current.clone());
+        }
+        Some((path, lookups))
+    }
+}
+
+pub struct TableSolver {
+    pub table: DistanceTable,
+}
+
+impl Solver for TableSolver {
+    fn name(&self) -> &'static str {
+        "Distance table"
+    }
+
+    fn solve(
+        &mut self,
+        start: Puzzle,
+        end: &Puzzle,
+        _: &mut dyn Heuristic,
+        _: &Limits,
+        _: &mut dyn Progress,
+    ) -> Outcome {
+        if end.flat != self.table.goal {
+            return Outcome::Unsolvable(Stats::default());
+        }
+        match self.table.solve(&start) {
+            Some((path, lookups)) => {
+                let stats = Stats {
+                    total_opened: lookups,
+                    max_states: path.len(),
+                };
+                Outcome::Solved(Solution::from_path(stats, path))
+            }
+            None => Outcome::Unsolvable(Stats::default()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::hardest_classic;
+    use crate::puzzle::PuzzleKind;
+
+    #[test]
+    fn test_classic_histogram() {
+        let goal = Puzzle::new(PuzzleKind::Classic, 3);
+        let (table, histogram) = DistanceTable::build(&goal).unwrap();
+
+        assert_eq!(histogram.iter().sum::<usize>(), 181440);
+        assert_eq!(histogram.len() - 1, 31);
+        assert_eq!(histogram[31], 2);
+
+        let (start, _) = hardest_classic();
+        let (path, _) = table.solve(&start).unwrap();
+        assert_eq!(path.len() - 1, 31);
+        assert_eq!(path.last(), Some(&goal));
+    }
+
+    #[test]
+    fn test_save_load() {
+        let goal = Puzzle::new(PuzzleKind::Classic, 2);
+        let (table, histogram) = DistanceTable::build(&goal).unwrap();
+        let path = std::env::temp_dir().join("n-puzzle-table-2x2");
+        table.save(&path).unwrap();
+        let loaded = DistanceTable::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(histogram.iter().sum::<usize>(), 12);
+        assert_eq!(loaded.goal, goal.flat);
+        assert_eq!(loaded.cells, table.cells);
+    }
+
+    #[test]
+    fn test_load_bad_size() {
+        let path = std::env::temp_dir().join("n-puzzle-table-bad");
+        fs::write(&path, b"NPDT\x05").unwrap();
+        let loaded = DistanceTable::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert!(loaded.is_err());
+    }
+}
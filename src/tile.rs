@@ -1,3 +1,4 @@
+use super::goal::Layout;
 use super::puzzle::Puzzle;
 
 pub struct TilePosition {
@@ -8,36 +9,49 @@ pub struct TilePosition {
 pub struct Tile {
     pub value: u16,
     pub pos: TilePosition,
-    pub goal: TilePosition,
+    // none for a tile free to end anywhere
+    pub goal: Option<TilePosition>,
 }
 
 impl Tile {
-    pub fn new(puzzle: &Puzzle, col: usize, row: usize) -> Tile {
+    pub fn new(
+        puzzle: &Puzzle,
+        layout: &Layout,
+        col: usize,
+        row: usize,
+    ) -> Tile {
         let value = puzzle.flat[col + row * puzzle.n];
-        let goal_index = puzzle.end[value as usize];
         Tile {
             value,
             pos: TilePosition { col, row },
-            goal: TilePosition {
+            goal: layout.cell(value).map(|goal_index| TilePosition {
                 col: goal_index % puzzle.n,
                 row: goal_index / puzzle.n,
-            },
+            }),
         }
     }
 
     pub fn is_in_row_conflict_with(&self, other: &Tile) -> bool {
+        let (goal, other_goal) = match (&self.goal, &other.goal) {
+            (Some(goal), Some(other_goal)) => (goal, other_goal),
+            _ => return false,
+        };
         self.value != 0
             && other.value != 0
-            && self.goal.row == self.pos.row
-            && self.goal.row == other.goal.row
-            && self.goal.col > other.goal.col
+            && goal.row == self.pos.row
+            && goal.row == other_goal.row
+            && goal.col > other_goal.col
     }
 
     pub fn is_in_col_conflict_with(&self, other: &Tile) -> bool {
+        let (goal, other_goal) = match (&self.goal, &other.goal) {
+            (Some(goal), Some(other_goal)) => (goal, other_goal),
+            _ => return false,
+        };
         self.value != 0
             && other.value != 0
-            && self.goal.col == self.pos.col
-            && self.goal.col == other.goal.col
-            && self.goal.row > other.goal.row
+            && goal.col == self.pos.col
+            && goal.col == other_goal.col
+            && goal.row > other_goal.row
     }
 }
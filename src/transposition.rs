@@ -0,0 +1,72 @@
+// What IDA* learned about a board: the lowest g it was reached with, the
+// machine state and iteration that g was explored in, and a lower bound on
+// its distance to the goal that can be better than the heuristic
+//
+#[derive(Clone, Copy, Default)]
+pub struct Entry {
+    pub key: u64,
+    pub g: u32,
+    pub h: u32,
+    pub state: u32,
+    pub iteration: u32,
+}
+
+// Fixed number of entries indexed by zobrist key, a new board takes the
+// slot of the one before it so memory never grows during the search
+//
+pub struct TranspositionTable {
+    entries: Vec<Entry>,
+    pub hits: usize,
+}
+
+impl TranspositionTable {
+    pub fn new(size: usize) -> TranspositionTable {
+        TranspositionTable {
+            entries: vec![Entry::default(); size],
+            hits: 0,
+        }
+    }
+
+    fn slot(&self, key: u64) -> usize {
+        (key % self.entries.len() as u64) as usize
+    }
+
+    pub fn get(&self, key: u64) -> Option<&Entry> {
+        // empty slots have the key 0, no real board is expected to hash to it
+        let entry = &self.entries[self.slot(key)];
+        if entry.key == key && key != 0 {
+            Some(entry)
+        } else {
+            None
+        }
+    }
+
+    pub fn insert(&mut self, entry: Entry) {
+        let slot = self.slot(entry.key);
+        self.entries[slot] = entry;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replace() {
+        let mut table = TranspositionTable::new(4);
+        let entry = |key, g| Entry {
+            key,
+            g,
+            ..Entry::default()
+        };
+        table.insert(entry(5, 1));
+        table.insert(entry(6, 2));
+
+        assert_eq!(table.get(5).map(|e| e.g), Some(1));
+        assert!(table.get(7).is_none());
+        table.insert(entry(9, 3));
+        assert!(table.get(5).is_none());
+        assert_eq!(table.get(9).map(|e| e.g), Some(3));
+        assert_eq!(table.get(6).map(|e| e.g), Some(2));
+    }
+}
@@ -1,3 +1,4 @@
+use super::goal::{GoalSpec, Pattern};
 use super::inversions;
 use super::puzzle::Puzzle;
 
@@ -38,15 +39,55 @@ pub fn check_puzzle(puzzle: &Puzzle, goal: &Puzzle) -> bool {
 	let (_, goal_inversions) =
 		inversions::merge_count_inversion(&goal.flat);
 
-	is_nxn_length(puzzle)
+	puzzle.n == goal.n
+		&& is_nxn_length(puzzle)
 		&& is_from_0_to_nxn(sorted)
 		&& is_solvable(puzzle, inversions)
 			== is_solvable(goal, goal_inversions)
 }
 
+// The boards matching the pattern worth checking: with two free tiles
+// besides the blank they can end either way round, one of the two has the
+// right parity. Otherwise the one or two missing tiles go in every order.
+//
+fn completions(pattern: &Pattern) -> Option<Vec<Vec<u16>>> {
+	let missing = GoalSpec::missing(pattern);
+	if missing.iter().filter(|&&t| t != 0).count() >= 2 {
+		return None;
+	}
+	let filled = GoalSpec::fill(pattern);
+	let mut swapped = filled.clone();
+	let free: Vec<usize> = (0..pattern.len())
+		.filter(|&i| pattern[i].is_none())
+		.collect();
+	if let [a, b] = free[..] {
+		swapped.swap(a, b);
+	}
+	Some(vec![filled, swapped])
+}
+
+pub fn check_puzzle_spec(puzzle: &Puzzle, spec: &GoalSpec) -> bool {
+	let (sorted, _) = inversions::merge_count_inversion(&puzzle.flat);
+	let well_formed = puzzle.n == spec.n
+		&& is_nxn_length(puzzle)
+		&& is_from_0_to_nxn(sorted);
+
+	well_formed
+		&& spec
+			.patterns
+			.iter()
+			.any(|pattern| match completions(pattern) {
+				None => true,
+				Some(boards) => boards.into_iter().any(|flat| {
+					check_puzzle(puzzle, &Puzzle::from_flat(spec.n, flat))
+				}),
+			})
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use crate::puzzle::PuzzleKind;
 
 	#[test]
 	fn test_blank_row() {
@@ -100,4 +141,35 @@ mod tests {
 			false
 		);
 	}
+
+	#[test]
+	fn test_check_puzzle_size() {
+		let solved = Puzzle::new(PuzzleKind::Classic, 4);
+		let smaller = Puzzle::new(PuzzleKind::Classic, 3);
+
+		assert!(check_puzzle(&solved, &solved));
+		assert!(!check_puzzle(&solved, &smaller));
+	}
+
+	#[test]
+	fn test_check_puzzle_spec() {
+		let swapped = Puzzle::from_matrix(
+			3,
+			vec![vec![2, 1, 3], vec![4, 5, 6], vec![7, 8, 0]],
+		);
+		let exact = GoalSpec::exact(&Puzzle::from_matrix(
+			3,
+			vec![vec![1, 2, 3], vec![4, 5, 6], vec![7, 8, 0]],
+		));
+		let mut last_two_free = exact.clone();
+		last_two_free.patterns[0][6] = None;
+		last_two_free.patterns[0][7] = None;
+		let mut blank_free = exact.clone();
+		blank_free.patterns[0][7] = None;
+		blank_free.patterns[0][8] = None;
+
+		assert!(!check_puzzle_spec(&swapped, &exact));
+		assert!(check_puzzle_spec(&swapped, &last_two_free));
+		assert!(!check_puzzle_spec(&swapped, &blank_free));
+	}
 }
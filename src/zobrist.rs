@@ -0,0 +1,106 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::{BuildHasherDefault, Hasher};
+
+// [https://en.wikipedia.org/wiki/Zobrist_hashing]
+//
+// Every tile in every cell gets a random 64 bits key and a board is the xor
+// of the keys of its tiles. Sliding a tile only changes two of them so the
+// hash of a board is kept up to date in O(1) as moves are made.
+//
+// The keys are derived from the tile and the cell instead of being drawn
+// from a table, which would not fit for the largest boards: the same board
+// always gets the same hash, from one run to the other too.
+//
+
+// [https://prng.di.unimi.it/splitmix64.c]
+//
+fn splitmix64(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+// The blank has no key, where it is follows from the other tiles
+//
+pub fn key(tile: u16, cell: usize) -> u64 {
+    match tile {
+        0 => 0,
+        _ => splitmix64((tile as u64) << 32 | cell as u64),
+    }
+}
+
+pub fn hash(flat: &[u16]) -> u64 {
+    flat.iter()
+        .enumerate()
+        .fold(0, |acc, (i, &t)| acc ^ key(t, i))
+}
+
+// What changes when `tile` slides from one cell to another
+//
+pub fn slide(tile: u16, from: usize, to: usize) -> u64 {
+    key(tile, from) ^ key(tile, to)
+}
+
+// Hasher for keys that already are well mixed 64 bits values, they are
+// used as they are instead of being hashed a second time
+//
+#[derive(Default)]
+pub struct ZobristHasher {
+    hash: u64,
+}
+
+impl Hasher for ZobristHasher {
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.hash = splitmix64(self.hash ^ b as u64);
+        }
+    }
+
+    fn write_u64(&mut self, n: u64) {
+        self.hash ^= n;
+    }
+}
+
+pub type BuildZobristHasher = BuildHasherDefault<ZobristHasher>;
+pub type ZobristSet<T> = HashSet<T, BuildZobristHasher>;
+pub type ZobristMap<K, V> = HashMap<K, V, BuildZobristHasher>;
+
+// The packed boards up to 4x4 are their own key, they only need mixing
+//
+pub fn mix(packed: u64) -> u64 {
+    splitmix64(packed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::moves::Move;
+    use crate::puzzle::{Puzzle, PuzzleKind};
+
+    #[test]
+    fn test_incremental_hash() {
+        let mut p = Puzzle::new(PuzzleKind::Snail, 5);
+        assert_eq!(p.key, hash(&p.flat));
+        for m in
+            [Move::Up, Move::Left, Move::Left, Move::Down, Move::Right]
+        {
+            m.apply(&mut p);
+            assert_eq!(p.key, hash(&p.flat));
+        }
+
+        // same board reached by different moves
+        let mut q = p.clone();
+        Move::Up.apply(&mut q);
+        Move::Down.apply(&mut q);
+        let mut set = ZobristSet::default();
+        set.insert(p.clone());
+        assert_eq!(p, q);
+        assert_ne!(p.was, q.was);
+        assert!(set.contains(&q));
+    }
+}